@@ -0,0 +1,78 @@
+// Copyright 2019-present, OVH SAS
+// All rights reserved.
+//
+// This OVH Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use serde_derive::Serialize;
+use serde_gelf::{FlattenOptions, GelfRecord, GelfRecordBuilder};
+
+#[derive(Serialize)]
+struct Request {
+    method: &'static str,
+    path: &'static str,
+    status: u16,
+    duration: f64,
+    user: User,
+    tags: Vec<&'static str>,
+}
+
+#[derive(Serialize)]
+struct User {
+    id: u64,
+    name: &'static str,
+    roles: Vec<&'static str>,
+}
+
+fn request() -> Request {
+    Request {
+        method: "GET",
+        path: "/api/v1/users/42",
+        status: 200,
+        duration: 0.0123,
+        user: User { id: 42, name: "John", roles: vec!["admin", "user"] },
+        tags: vec!["api", "v1", "users"],
+    }
+}
+
+fn flatten(c: &mut Criterion) {
+    let value = request();
+    let mut group = c.benchmark_group("flatten");
+    group.bench_function("to_flat_dict", |b| {
+        b.iter(|| serde_json::to_vec(&serde_gelf::to_flat_dict(black_box(&value)).unwrap()).unwrap())
+    });
+    group.bench_function("to_flat_vec", |b| {
+        b.iter(|| serde_gelf::to_flat_vec(black_box(&value)).unwrap())
+    });
+    group.finish();
+}
+
+fn record(c: &mut Criterion) {
+    let value = request();
+    let mut group = c.benchmark_group("record");
+    group.bench_function("add_additional_fields", |b| {
+        b.iter(|| {
+            let fields = serde_gelf::to_flat_dict_with(black_box(&value), &FlattenOptions::new().set_ldp_suffixes(false)).unwrap();
+            let rec = GelfRecord::new().set_message("request".into()).extend_additional_fields(fields);
+            serde_json::to_vec(&rec).unwrap()
+        })
+    });
+    group.bench_function("write_with_fields", |b| {
+        let options = FlattenOptions::default();
+        b.iter(|| {
+            let rec = GelfRecord::new().set_message("request".into());
+            let mut payload = Vec::with_capacity(512);
+            rec.write_with_fields(&mut payload, black_box(&value), &options).unwrap();
+            payload
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, flatten, record);
+criterion_main!(benches);
@@ -0,0 +1,288 @@
+// Copyright 2019-present, OVH SAS
+// All rights reserved.
+//
+// This OVH Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Derive macro implementing `serde_gelf::GelfRecordGetter`, use it via the `derive` feature of
+//! [`serde_gelf`](https://docs.rs/serde_gelf).
+#![deny(warnings, missing_docs)]
+extern crate proc_macro;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Fields, Ident, LitStr, Type};
+
+/// Implement `GelfRecordGetter` on a struct with named fields, so that it can be converted into
+/// a `GelfRecord` using `GelfRecord::from(&value)`.
+///
+/// Fields are tagged using the `gelf` attribute:
+///
+/// - `#[gelf(message)]`: the `short_message`, any `Display` type, mandatory.
+/// - `#[gelf(full_message)]`: the `full_message`, any `Display` type or an `Option` of it.
+/// - `#[gelf(level)]`: the level, any type convertible into `GelfLevel` such as `log::Level`.
+/// - `#[gelf(timestamp)]`: the timestamp, a `f64`, a `SystemTime`, a `chrono::DateTime<Utc>` or
+///   a `time::OffsetDateTime` according to the enabled features.
+/// - `#[gelf(host)]` and `#[gelf(facility)]`: any `Display` type.
+/// - `#[gelf(skip)]`: the field is ignored.
+/// - `#[gelf(rename = "name")]`: the additional field name.
+///
+/// Every other field is a `Serialize` additional field, flattened when converted into a
+/// `GelfRecord`. As `GelfRecordGetter::additional_fields` can't fail, a field whose
+/// serialization fails is left out. The level defaults to `Alert`, the
+/// timestamp to the conversion time, the facility, file and line to the deriving location.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_gelf::{GelfLevel, GelfRecord, GelfRecordGetter};
+///
+/// #[derive(serde_gelf::GelfRecord)]
+/// struct Login {
+///     #[gelf(message)]
+///     message: String,
+///     #[gelf(level)]
+///     level: GelfLevel,
+///     #[gelf(rename = "user")]
+///     user_name: String,
+///     #[gelf(skip)]
+///     password: String,
+/// }
+///
+/// let event = Login {
+///     message: "logged in".into(),
+///     level: GelfLevel::Notice,
+///     user_name: "John".into(),
+///     password: "secret".into(),
+/// };
+/// let rec = GelfRecord::from(&event);
+/// assert_eq!(rec.message(), "logged in");
+/// assert_eq!(rec.level(), GelfLevel::Notice);
+/// assert_eq!(rec.additional_fields().len(), 1);
+/// assert!(rec.additional_fields().contains_key(&serde_value::Value::String("_user".into())));
+/// ```
+///
+/// # Errors
+///
+/// The derive fails to compile without a `#[gelf(message)]` field:
+///
+/// ```compile_fail
+/// #[derive(serde_gelf::GelfRecord)]
+/// struct Login {
+///     user_name: String,
+/// }
+/// ```
+///
+/// when a role is given to two fields, or twice to the same field:
+///
+/// ```compile_fail
+/// #[derive(serde_gelf::GelfRecord)]
+/// struct Login {
+///     #[gelf(message)]
+///     message: String,
+///     #[gelf(message)]
+///     summary: String,
+/// }
+/// ```
+///
+/// ```compile_fail
+/// #[derive(serde_gelf::GelfRecord)]
+/// struct Login {
+///     #[gelf(message, level)]
+///     message: String,
+/// }
+/// ```
+///
+/// or when a `gelf` attribute is unknown:
+///
+/// ```compile_fail
+/// #[derive(serde_gelf::GelfRecord)]
+/// struct Login {
+///     #[gelf(message)]
+///     message: String,
+///     #[gelf(flatten)]
+///     user_name: String,
+/// }
+/// ```
+#[proc_macro_derive(GelfRecord, attributes(gelf))]
+pub fn derive_gelf_record(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+/// Role of a field according to its `gelf` attribute.
+enum Role {
+    Message,
+    FullMessage,
+    Level,
+    Timestamp,
+    Host,
+    Facility,
+    Skip,
+    Field(String),
+}
+
+impl Role {
+    fn name(&self) -> &'static str {
+        match self {
+            Role::Message => "message",
+            Role::FullMessage => "full_message",
+            Role::Level => "level",
+            Role::Timestamp => "timestamp",
+            Role::Host => "host",
+            Role::Facility => "facility",
+            Role::Skip => "skip",
+            Role::Field(_) => "rename",
+        }
+    }
+}
+
+fn role(field: &syn::Field, ident: &Ident) -> syn::Result<Role> {
+    let mut role = None;
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("gelf")) {
+        attr.parse_nested_meta(|meta| {
+            let found = if meta.path.is_ident("message") {
+                Role::Message
+            } else if meta.path.is_ident("full_message") {
+                Role::FullMessage
+            } else if meta.path.is_ident("level") {
+                Role::Level
+            } else if meta.path.is_ident("timestamp") {
+                Role::Timestamp
+            } else if meta.path.is_ident("host") {
+                Role::Host
+            } else if meta.path.is_ident("facility") {
+                Role::Facility
+            } else if meta.path.is_ident("skip") {
+                Role::Skip
+            } else if meta.path.is_ident("rename") {
+                Role::Field(meta.value()?.parse::<LitStr>()?.value())
+            } else {
+                return Err(meta.error("unknown gelf attribute"));
+            };
+            match role {
+                Some(ref previous) => Err(meta.error(format!("field already tagged as gelf({})", Role::name(previous)))),
+                None => {
+                    role = Some(found);
+                    Ok(())
+                }
+            }
+        })?;
+    }
+    Ok(role.unwrap_or_else(|| Role::Field(ident.to_string())))
+}
+
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path.qself.is_none() && path.path.segments.last().is_some_and(|segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => return Err(Error::new_spanned(&input.ident, "GelfRecord can only be derived for structs with named fields")),
+        },
+        _ => return Err(Error::new_spanned(&input.ident, "GelfRecord can only be derived for structs with named fields")),
+    };
+
+    let mut message = None;
+    let mut full_message = None;
+    let mut level = None;
+    let mut timestamp = None;
+    let mut host = None;
+    let mut facility = None;
+    let mut additional_fields = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let role = role(field, ident)?;
+        let slot = match role {
+            Role::Message => &mut message,
+            Role::FullMessage => &mut full_message,
+            Role::Level => &mut level,
+            Role::Timestamp => &mut timestamp,
+            Role::Host => &mut host,
+            Role::Facility => &mut facility,
+            Role::Skip => continue,
+            Role::Field(name) => {
+                additional_fields.push(quote! {
+                    if let Ok(value) = ::serde_gelf::__private::to_value(&self.#ident) {
+                        fields.insert(::serde_gelf::__private::Value::String(#name.to_string()), value);
+                    }
+                });
+                continue;
+            }
+        };
+        if slot.is_some() {
+            return Err(Error::new_spanned(ident, format!("duplicate gelf({}) field", role.name())));
+        }
+        *slot = Some(field);
+    }
+
+    let message = match message {
+        Some(field) => {
+            let ident = &field.ident;
+            quote! { ::std::string::ToString::to_string(&self.#ident) }
+        }
+        None => return Err(Error::new_spanned(&input.ident, "missing #[gelf(message)] field")),
+    };
+    let level = match level {
+        Some(field) => {
+            let ident = &field.ident;
+            quote! { ::serde_gelf::GelfLevel::from(::std::clone::Clone::clone(&self.#ident)) }
+        }
+        None => quote! { ::std::default::Default::default() },
+    };
+    let timestamp = match timestamp {
+        Some(field) => {
+            let ident = &field.ident;
+            quote! { ::serde_gelf::__private::AsTimestamp::as_timestamp(&self.#ident) }
+        }
+        None => quote! { ::serde_gelf::__private::AsTimestamp::as_timestamp(&::std::time::SystemTime::now()) },
+    };
+    let facility = match facility {
+        Some(field) => {
+            let ident = &field.ident;
+            quote! { ::std::string::ToString::to_string(&self.#ident) }
+        }
+        None => quote! { module_path!().to_string() },
+    };
+    let full_message = full_message.map(|field| {
+        let ident = &field.ident;
+        let value = match is_option(&field.ty) {
+            true => quote! { self.#ident.as_ref().map(::std::string::ToString::to_string) },
+            false => quote! { Some(::std::string::ToString::to_string(&self.#ident)) },
+        };
+        quote! { fn full_message(&self) -> Option<String> { #value } }
+    });
+    let host = host.map(|field| {
+        let ident = &field.ident;
+        quote! { fn host(&self) -> String { ::std::string::ToString::to_string(&self.#ident) } }
+    });
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::serde_gelf::GelfRecordGetter for #name #ty_generics #where_clause {
+            fn message(&self) -> String { #message }
+            fn timestamp(&self) -> f64 { #timestamp }
+            fn level(&self) -> ::serde_gelf::GelfLevel { #level }
+            fn additional_fields(&self) -> ::std::collections::BTreeMap<::serde_gelf::__private::Value, ::serde_gelf::__private::Value> {
+                let mut fields = ::std::collections::BTreeMap::new();
+                #(#additional_fields)*
+                fields
+            }
+            fn facility(&self) -> String { #facility }
+            fn line(&self) -> u32 { line!() }
+            fn file(&self) -> String { file!().to_string() }
+            #full_message
+            #host
+        }
+    })
+}
@@ -0,0 +1,200 @@
+// Copyright 2019-present, OVH SAS
+// All rights reserved.
+//
+// This OVH Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! GELF UDP chunking, see the
+//! [`GELF via UDP`](http://docs.graylog.org/en/3.0/pages/gelf.html#chunking) specification.
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Magic bytes identifying a GELF chunk.
+pub const CHUNK_MAGIC: [u8; 2] = [0x1e, 0x0f];
+/// Size of a chunk header: magic bytes, message id, sequence number and sequence count.
+pub const CHUNK_HEADER_SIZE: usize = 12;
+/// Maximum number of chunks a message can be split into.
+pub const MAX_CHUNKS: usize = 128;
+
+static MESSAGE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Generate a 8 bytes message id, unique enough to tell concurrent messages apart.
+pub fn message_id() -> [u8; 8] {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_usize(MESSAGE_COUNTER.fetch_add(1, Ordering::Relaxed));
+    hasher.write_u32(std::process::id());
+    if let Ok(now) = SystemTime::now().duration_since(UNIX_EPOCH) {
+        hasher.write_u128(now.as_nanos());
+    }
+    hasher.finish().to_be_bytes()
+}
+
+/// Split a payload into datagrams of at most `mtu` bytes.
+///
+/// A payload which fits in a single datagram is returned as is, otherwise it is split into GELF
+/// chunks sharing the same message id. Fails if more than `MAX_CHUNKS`
+/// chunks are required.
+pub fn chunks(payload: &[u8], mtu: usize) -> io::Result<Vec<Vec<u8>>> {
+    if payload.len() <= mtu {
+        return Ok(vec![payload.to_vec()]);
+    }
+    let chunk_size = mtu.saturating_sub(CHUNK_HEADER_SIZE).max(1);
+    let count = payload.len().div_ceil(chunk_size);
+    if count > MAX_CHUNKS {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("message requires {} chunks, GELF allows at most {}", count, MAX_CHUNKS),
+        ));
+    }
+
+    let id = message_id();
+    Ok(payload.chunks(chunk_size).enumerate().map(|(sequence, data)| {
+        let mut chunk = Vec::with_capacity(CHUNK_HEADER_SIZE + data.len());
+        chunk.extend_from_slice(&CHUNK_MAGIC);
+        chunk.extend_from_slice(&id);
+        chunk.push(sequence as u8);
+        chunk.push(count as u8);
+        chunk.extend_from_slice(data);
+        chunk
+    }).collect())
+}
+
+/// Return whether a datagram is a GELF chunk.
+pub fn is_chunk(datagram: &[u8]) -> bool {
+    datagram.starts_with(&CHUNK_MAGIC)
+}
+
+/// Chunks received for a message.
+struct Pending {
+    started: Instant,
+    chunks: Vec<Option<Vec<u8>>>,
+    received: usize,
+    bytes: usize,
+}
+
+/// Reassemble GELF chunks into payloads.
+///
+/// Messages which are not complete within `timeout` are dropped, as well as the oldest messages
+/// when the chunks held exceed `max_bytes`.
+pub struct Reassembler {
+    pending: HashMap<[u8; 8], Pending>,
+    timeout: Duration,
+    max_bytes: usize,
+    bytes: usize,
+    dropped: usize,
+}
+
+impl Reassembler {
+    /// Create a reassembler.
+    pub fn new(timeout: Duration, max_bytes: usize) -> Reassembler {
+        Reassembler { pending: HashMap::new(), timeout, max_bytes, bytes: 0, dropped: 0 }
+    }
+
+    /// Set the time allowed to receive every chunk of a message.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Set the maximum size of the chunks held.
+    pub fn set_max_bytes(&mut self, max_bytes: usize) {
+        self.max_bytes = max_bytes;
+    }
+
+    /// Return the number of messages dropped because of the timeout or the memory cap.
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Return the number of incomplete messages.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Handle a chunk, returning the payload once every chunk of its message is received.
+    pub fn push(&mut self, chunk: &[u8], now: Instant) -> io::Result<Option<Vec<u8>>> {
+        self.expire(now);
+        if chunk.len() < CHUNK_HEADER_SIZE || !is_chunk(chunk) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid GELF chunk header"));
+        }
+        let mut id = [0; 8];
+        id.copy_from_slice(&chunk[2..10]);
+        let (sequence, count) = (usize::from(chunk[10]), usize::from(chunk[11]));
+        if count == 0 || count > MAX_CHUNKS || sequence >= count {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid GELF chunk sequence {}/{}", sequence, count),
+            ));
+        }
+        let data = &chunk[CHUNK_HEADER_SIZE..];
+        if data.len() > self.max_bytes {
+            self.dropped += 1;
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "GELF chunk exceeds the memory cap"));
+        }
+        while self.bytes + data.len() > self.max_bytes && self.evict_oldest() {}
+
+        let pending = self.pending.entry(id).or_insert_with(|| Pending {
+            started: now,
+            chunks: vec![None; count],
+            received: 0,
+            bytes: 0,
+        });
+        if pending.chunks.len() != count {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "GELF chunk count mismatch"));
+        }
+        if pending.chunks[sequence].is_none() {
+            pending.chunks[sequence] = Some(data.to_vec());
+            pending.received += 1;
+            pending.bytes += data.len();
+            self.bytes += data.len();
+        }
+        if pending.received < count {
+            return Ok(None);
+        }
+
+        let pending = self.pending.remove(&id).expect("pending message");
+        self.bytes -= pending.bytes;
+        let mut payload = Vec::with_capacity(pending.bytes);
+        for data in pending.chunks.into_iter().flatten() {
+            payload.extend_from_slice(&data);
+        }
+        Ok(Some(payload))
+    }
+
+    /// Drop the messages older than the timeout.
+    fn expire(&mut self, now: Instant) {
+        let timeout = self.timeout;
+        let before = self.pending.len();
+        let mut freed = 0;
+        self.pending.retain(|_, pending| {
+            let alive = now.duration_since(pending.started) < timeout;
+            if !alive {
+                freed += pending.bytes;
+            }
+            alive
+        });
+        self.bytes -= freed;
+        self.dropped += before - self.pending.len();
+    }
+
+    /// Drop the oldest message, returning `false` if there is none.
+    fn evict_oldest(&mut self) -> bool {
+        let oldest = self.pending.iter().min_by_key(|(_, pending)| pending.started).map(|(id, _)| *id);
+        match oldest.and_then(|id| self.pending.remove(&id)) {
+            Some(pending) => {
+                self.bytes -= pending.bytes;
+                self.dropped += 1;
+                true
+            }
+            None => false,
+        }
+    }
+}
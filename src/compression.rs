@@ -0,0 +1,79 @@
+// Copyright 2019-present, OVH SAS
+// All rights reserved.
+//
+// This OVH Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use std::borrow::Cow;
+use std::io::{self, Read, Write};
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+
+/// Compression applied to GELF payloads before they are sent, or detected on received ones.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Compression {
+    /// Send payloads as is.
+    None,
+    /// Compress payloads using GZIP.
+    Gzip,
+    /// Compress payloads using ZLIB.
+    Zlib,
+}
+
+/// Payloads are not compressed by default.
+impl Default for Compression {
+    fn default() -> Compression {
+        Compression::None
+    }
+}
+
+impl Compression {
+    /// Compress a payload, borrowing it when no compression is applied.
+    pub fn compress(self, payload: &[u8]) -> io::Result<Cow<'_, [u8]>> {
+        match self {
+            Compression::None => Ok(Cow::Borrowed(payload)),
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(payload)?;
+                encoder.finish().map(Cow::Owned)
+            }
+            Compression::Zlib => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(payload)?;
+                encoder.finish().map(Cow::Owned)
+            }
+        }
+    }
+
+    /// Detect the compression of a payload from its magic bytes.
+    pub fn detect(payload: &[u8]) -> Compression {
+        match *payload {
+            [0x1f, 0x8b, ..] => Compression::Gzip,
+            [cmf, flg, ..] if cmf & 0x0f == 8 && (u16::from(cmf) << 8 | u16::from(flg)) % 31 == 0 => Compression::Zlib,
+            _ => Compression::None,
+        }
+    }
+
+    /// Decompress a payload, failing if it inflates beyond `limit` bytes.
+    pub fn decompress(self, payload: &[u8], limit: usize) -> io::Result<Vec<u8>> {
+        let reader: Box<dyn Read + '_> = match self {
+            Compression::None => Box::new(payload),
+            Compression::Gzip => Box::new(GzDecoder::new(payload)),
+            Compression::Zlib => Box::new(ZlibDecoder::new(payload)),
+        };
+        let mut decompressed = Vec::new();
+        reader.take(limit as u64 + 1).read_to_end(&mut decompressed)?;
+        if decompressed.len() > limit {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("decompressed payload exceeds {} bytes", limit),
+            ));
+        }
+        Ok(decompressed)
+    }
+}
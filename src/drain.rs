@@ -0,0 +1,143 @@
+// Copyright 2019-present, OVH SAS
+// All rights reserved.
+//
+// This OVH Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+
+use serde_value::Value;
+use slog::{Drain, Key, OwnedKVList, Record, SerdeValue, KV};
+
+use crate::level::GelfLevel;
+use crate::record::{GelfRecord, GelfRecordBuilder};
+use crate::sender::GelfSink;
+
+/// A [`slog::Drain`](https://docs.rs/slog/2/slog/trait.Drain.html) which sends every record as a
+/// [`GelfRecord`](struct.GelfRecord.html) to a [`GelfSink`](trait.GelfSink.html).
+///
+/// The key-value pairs of the logger and of the record are flattened into the additional fields
+/// like [`to_flat_dict`](fn.to_flat_dict.html) does, record values overriding logger values.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate slog;
+/// extern crate serde_gelf;
+///
+/// use std::io;
+/// use std::sync::{Arc, Mutex};
+/// use serde_gelf::{GelfDrain, GelfRecord, GelfRecordGetter, GelfSink};
+/// use serde_value::Value;
+/// use slog::Drain;
+///
+/// #[derive(Clone, Default)]
+/// struct Capture(Arc<Mutex<Vec<GelfRecord>>>);
+///
+/// impl GelfSink for Capture {
+///     fn send(&self, record: &GelfRecord) -> io::Result<()> {
+///         self.0.lock().unwrap().push(record.clone());
+///         Ok(())
+///     }
+/// }
+///
+/// fn main() {
+///     let capture = Capture::default();
+///     let drain = GelfDrain::new(capture.clone()).fuse();
+///     let logger = slog::Logger::root(drain, o!("app" => "api", "status" => "pending"));
+///
+///     info!(logger, "request handled"; "status" => 200, "user" => "bob");
+///
+///     let records = capture.0.lock().unwrap();
+///     let field = |name: &str| records[0].additional_fields().get(&Value::String(name.into())).cloned();
+///     assert_eq!(records[0].message(), "request handled");
+///     assert_eq!(field("_app"), Some(Value::String("api".into())));
+///     assert_eq!(field("_user"), Some(Value::String("bob".into())));
+///     // The record value wins over the logger value.
+///     assert_eq!(field("_status"), Some(Value::I32(200)));
+/// }
+/// ```
+pub struct GelfDrain<S> {
+    sink: S,
+}
+
+impl<S: GelfSink> GelfDrain<S> {
+    /// Create a drain sending records to the given sink.
+    pub fn new(sink: S) -> GelfDrain<S> {
+        GelfDrain { sink }
+    }
+}
+
+impl<S: GelfSink> Drain for GelfDrain<S> {
+    type Ok = ();
+    type Err = io::Error;
+
+    fn log(&self, record: &Record, values: &OwnedKVList) -> io::Result<()> {
+        let mut serializer = FieldSerializer(BTreeMap::new());
+        record.kv().serialize(record, &mut serializer).map_err(to_io_error)?;
+        values.serialize(record, &mut serializer).map_err(to_io_error)?;
+
+        let record = GelfRecord::new()
+            .set_facility(record.module().to_string())
+            .set_file(record.file().to_string())
+            .set_line(record.line())
+            .set_level(GelfLevel::from(record.level()))
+            .set_message(record.msg().to_string())
+            .add_additional_fields(serializer.0);
+        self.sink.send(&record)
+    }
+}
+
+fn to_io_error(err: slog::Error) -> io::Error {
+    match err {
+        slog::Error::Io(err) => err,
+        err => io::Error::other(err.to_string()),
+    }
+}
+
+/// Collect key-value pairs, keeping the first value serialized for a key.
+///
+/// Record values are serialized before logger values, which are themselves serialized from the
+/// most specific logger to the root one.
+struct FieldSerializer(BTreeMap<Value, Value>);
+
+impl FieldSerializer {
+    fn insert(&mut self, key: Key, value: Value) -> slog::Result {
+        self.0.entry(Value::String(key.to_string())).or_insert(value);
+        Ok(())
+    }
+}
+
+impl slog::Serializer for FieldSerializer {
+    fn emit_usize(&mut self, key: Key, val: usize) -> slog::Result { self.insert(key, Value::U64(val as u64)) }
+    fn emit_isize(&mut self, key: Key, val: isize) -> slog::Result { self.insert(key, Value::I64(val as i64)) }
+    fn emit_bool(&mut self, key: Key, val: bool) -> slog::Result { self.insert(key, Value::Bool(val)) }
+    fn emit_char(&mut self, key: Key, val: char) -> slog::Result { self.insert(key, Value::Char(val)) }
+    fn emit_u8(&mut self, key: Key, val: u8) -> slog::Result { self.insert(key, Value::U8(val)) }
+    fn emit_i8(&mut self, key: Key, val: i8) -> slog::Result { self.insert(key, Value::I8(val)) }
+    fn emit_u16(&mut self, key: Key, val: u16) -> slog::Result { self.insert(key, Value::U16(val)) }
+    fn emit_i16(&mut self, key: Key, val: i16) -> slog::Result { self.insert(key, Value::I16(val)) }
+    fn emit_u32(&mut self, key: Key, val: u32) -> slog::Result { self.insert(key, Value::U32(val)) }
+    fn emit_i32(&mut self, key: Key, val: i32) -> slog::Result { self.insert(key, Value::I32(val)) }
+    fn emit_f32(&mut self, key: Key, val: f32) -> slog::Result { self.insert(key, Value::F32(val)) }
+    fn emit_u64(&mut self, key: Key, val: u64) -> slog::Result { self.insert(key, Value::U64(val)) }
+    fn emit_i64(&mut self, key: Key, val: i64) -> slog::Result { self.insert(key, Value::I64(val)) }
+    fn emit_f64(&mut self, key: Key, val: f64) -> slog::Result { self.insert(key, Value::F64(val)) }
+    fn emit_str(&mut self, key: Key, val: &str) -> slog::Result { self.insert(key, Value::String(val.to_string())) }
+    fn emit_unit(&mut self, key: Key) -> slog::Result { self.insert(key, Value::Unit) }
+    fn emit_none(&mut self, key: Key) -> slog::Result { self.insert(key, Value::Option(None)) }
+    fn emit_arguments(&mut self, key: Key, val: &fmt::Arguments) -> slog::Result {
+        self.insert(key, Value::String(val.to_string()))
+    }
+    fn emit_serde(&mut self, key: Key, value: &dyn SerdeValue) -> slog::Result {
+        let value = serde_value::to_value(value.as_serde()).map_err(|_| slog::Error::Other)?;
+        self.insert(key, value)
+    }
+}
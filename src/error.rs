@@ -0,0 +1,56 @@
+// Copyright 2019-present, OVH SAS
+// All rights reserved.
+//
+// This OVH Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use std::fmt;
+
+use serde_value::SerializerError;
+
+/// Errors raised while building or serializing a [`GelfRecord`](struct.GelfRecord.html).
+#[derive(Debug)]
+pub enum Error {
+    /// A value could not be converted or flattened into additional fields.
+    Flatten(SerializerError),
+    /// Some additional field names do not follow the GELF specification.
+    InvalidFields(Vec<String>),
+    /// A record could not be serialized or parsed as JSON.
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Flatten(err) => write!(f, "failed to flatten additional fields: {}", err),
+            Error::InvalidFields(fields) => write!(f, "invalid additional field names: {}", fields.join(", ")),
+            Error::Json(err) => write!(f, "JSON error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Flatten(err) => Some(err),
+            Error::InvalidFields(_) => None,
+            Error::Json(err) => Some(err),
+        }
+    }
+}
+
+impl From<SerializerError> for Error {
+    fn from(err: SerializerError) -> Error {
+        Error::Flatten(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Error {
+        Error::Json(err)
+    }
+}
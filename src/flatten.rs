@@ -0,0 +1,341 @@
+// Copyright 2019-present, OVH SAS
+// All rights reserved.
+//
+// This OVH Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::ser::Error;
+use serde_value::{SerializerError, Value};
+
+use crate::validation::sanitize_field_name;
+
+/// How sequences are flattened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArrayMode {
+    /// One key per item, suffixed with the item index: `{"_c_0": true, "_c_1": false}`.
+    Indexed,
+    /// A single string made of the items joined with the given separator: `{"_c": "true,false"}`.
+    Joined(String),
+    /// A single JSON encoded string: `{"_c": "[true,false]"}`.
+    Json,
+}
+
+/// Set the default mode to `ArrayMode::Indexed`.
+impl Default for ArrayMode {
+    fn default() -> ArrayMode {
+        ArrayMode::Indexed
+    }
+}
+
+/// Options of [`to_flat_dict_with`](fn.to_flat_dict_with.html).
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_gelf::{ArrayMode, FlattenOptions};
+///
+/// let options = FlattenOptions::new()
+///     .set_separator(".")
+///     .set_max_depth(Some(3))
+///     .set_array_mode(ArrayMode::Json);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlattenOptions {
+    separator: String,
+    prefix: String,
+    max_depth: Option<usize>,
+    array_mode: ArrayMode,
+    sanitize: bool,
+    ldp_suffixes: bool,
+}
+
+/// Options used by [`to_flat_dict`](fn.to_flat_dict.html): `_` as separator and prefix, no
+/// maximum depth, indexed arrays and LDP suffixes only with the *ovh-ldp* feature.
+impl Default for FlattenOptions {
+    fn default() -> FlattenOptions {
+        FlattenOptions {
+            separator: "_".to_string(),
+            prefix: "_".to_string(),
+            max_depth: None,
+            array_mode: ArrayMode::default(),
+            sanitize: false,
+            ldp_suffixes: cfg!(feature = "ovh-ldp"),
+        }
+    }
+}
+
+impl FlattenOptions {
+    /// Construct the default options.
+    pub fn new() -> FlattenOptions {
+        FlattenOptions::default()
+    }
+
+    /// Set the separator inserted between nested keys.
+    pub fn set_separator(mut self, separator: &str) -> Self {
+        self.separator = separator.to_string();
+        self
+    }
+
+    /// Set the prefix of every key.
+    pub fn set_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    /// Set the maximum number of nested keys, deeper maps and sequences are JSON encoded as a
+    /// string.
+    ///
+    /// The top-level keys are always kept, flattening fails if the maximum depth is `Some(0)`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::collections::BTreeMap;
+    /// use serde_gelf::{to_flat_dict_with, FlattenOptions};
+    ///
+    /// let mut sub = BTreeMap::new();
+    /// sub.insert("b", vec![1, 2]);
+    /// let mut extra = BTreeMap::new();
+    /// extra.insert("a", sub);
+    ///
+    /// let flat = to_flat_dict_with(&extra, &FlattenOptions::new().set_max_depth(Some(1))).unwrap();
+    /// assert_eq!(
+    ///     flat.get(&serde_value::Value::String("_a".into())),
+    ///     Some(&serde_value::Value::String(r#"{"b":[1,2]}"#.into()))
+    /// );
+    ///
+    /// assert!(to_flat_dict_with(&extra, &FlattenOptions::new().set_max_depth(Some(0))).is_err());
+    /// ```
+    pub fn set_max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Set how sequences are flattened.
+    pub fn set_array_mode(mut self, array_mode: ArrayMode) -> Self {
+        self.array_mode = array_mode;
+        self
+    }
+
+    /// Rewrite the keys which are not valid additional field names, see
+    /// [`sanitize_field_name`](fn.sanitize_field_name.html).
+    ///
+    /// Flattening fails if two keys end up the same, whereas the last value wins without
+    /// sanitizing.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::collections::BTreeMap;
+    /// use serde_gelf::{to_flat_dict_with, FlattenOptions};
+    ///
+    /// let mut extra = BTreeMap::new();
+    /// extra.insert("user_name", "John");
+    /// extra.insert("user~name", "Jane");
+    ///
+    /// let err = to_flat_dict_with(&extra, &FlattenOptions::new().set_sanitize(true)).unwrap_err();
+    /// assert_eq!(err.to_string(), "`_user_name` and `_user~name` are both sanitized into `_user_name`");
+    ///
+    /// // Nothing is sanitized here, the keys are flattened into the same name.
+    /// let extra: BTreeMap<&str, serde_value::Value> = serde_json::from_str(r#"{"a": {"b": "x"}, "a_b": "y"}"#).unwrap();
+    /// let err = to_flat_dict_with(&extra, &FlattenOptions::new().set_sanitize(true)).unwrap_err();
+    /// assert_eq!(err.to_string(), "duplicate field `_a_b`, flattened from `a.b` and `a_b`");
+    ///
+    /// let flat = to_flat_dict_with(&extra, &FlattenOptions::new()).unwrap();
+    /// assert_eq!(flat.get(&serde_value::Value::String("_a_b".into())), Some(&serde_value::Value::String("y".into())));
+    /// ```
+    pub fn set_sanitize(mut self, sanitize: bool) -> Self {
+        self.sanitize = sanitize;
+        self
+    }
+
+    /// Append the LDP type suffix (`_bool`, `_double`, `_long` or `_float`) to the keys of numbers
+    /// and booleans, see the
+    /// [`field naming conventions`](https://docs.ovh.com/gb/en/logs-data-platform/field-naming-conventions/).
+    ///
+    /// Enabled by default with the *ovh-ldp* feature.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::collections::BTreeMap;
+    /// use serde_gelf::{to_flat_dict_with, FlattenOptions};
+    ///
+    /// let mut extra = BTreeMap::new();
+    /// extra.insert("retries", 3);
+    ///
+    /// let flat = to_flat_dict_with(&extra, &FlattenOptions::new().set_ldp_suffixes(true)).unwrap();
+    /// assert!(flat.contains_key(&serde_value::Value::String("_retries_long".into())));
+    ///
+    /// let flat = to_flat_dict_with(&extra, &FlattenOptions::new().set_ldp_suffixes(false)).unwrap();
+    /// assert!(flat.contains_key(&serde_value::Value::String("_retries".into())));
+    /// ```
+    pub fn set_ldp_suffixes(mut self, ldp_suffixes: bool) -> Self {
+        self.ldp_suffixes = ldp_suffixes;
+        self
+    }
+
+    /// Flatten a value.
+    pub fn flatten(&self, value: &Value) -> Result<BTreeMap<Value, Value>, SerializerError> {
+        self.check()?;
+        let mut flat = BTreeMap::new();
+        self.disassemble(&mut Vec::new(), value, &mut flat)?;
+        Ok(flat)
+    }
+
+    /// Flatten a value found at the given path.
+    pub(crate) fn disassemble(&self, path: &mut Vec<String>, value: &Value, flat: &mut BTreeMap<Value, Value>) -> Result<(), SerializerError> {
+        self.walk(path, value, &mut Flat { fields: flat, sources: HashMap::new() })
+    }
+
+    fn walk(&self, path: &mut Vec<String>, value: &Value, flat: &mut Flat<'_>) -> Result<(), SerializerError> {
+        let too_deep = self.is_too_deep(path);
+        match value {
+            Value::Option(Some(inner)) | Value::Newtype(inner) => self.walk(path, inner, flat),
+            Value::Map(_) | Value::Seq(_) if too_deep => self.insert(path, Value::String(to_json(value)?), flat),
+            Value::Map(tree) => {
+                for (key, inner) in tree {
+                    path.push(key_to_string(key)?);
+                    self.walk(path, inner, flat)?;
+                    path.pop();
+                }
+                Ok(())
+            }
+            Value::Seq(values) => match self.array_mode {
+                ArrayMode::Indexed => {
+                    for (index, inner) in values.iter().enumerate() {
+                        path.push(index.to_string());
+                        self.walk(path, inner, flat)?;
+                        path.pop();
+                    }
+                    Ok(())
+                }
+                ArrayMode::Joined(ref separator) => {
+                    let items = values.iter().map(value_to_string).collect::<Result<Vec<String>, SerializerError>>()?;
+                    self.insert(path, Value::String(items.join(separator)), flat)
+                }
+                ArrayMode::Json => self.insert(path, Value::String(to_json(value)?), flat),
+            },
+            _ => self.insert(path, value.clone(), flat),
+        }
+    }
+
+    /// Insert a flattened value, failing on a collision when sanitizing, the last value winning
+    /// otherwise.
+    fn insert(&self, path: &[String], value: Value, flat: &mut Flat<'_>) -> Result<(), SerializerError> {
+        let suffix = type_suffix(&value);
+        let key = self.key(path, suffix);
+        if self.sanitize {
+            if let Some(other) = flat.sources.get(&key) {
+                let (raw, other_raw) = (self.raw_key(path, suffix), self.raw_key(other, suffix));
+                return Err(SerializerError::custom(if raw != key || other_raw != key {
+                    format!("`{}` and `{}` are both sanitized into `{}`", other_raw, raw, key)
+                } else {
+                    format!("duplicate field `{}`, flattened from `{}` and `{}`", key, other.join("."), path.join("."))
+                }));
+            }
+            flat.sources.insert(key.clone(), path.to_vec());
+        }
+        flat.fields.insert(Value::String(key), value);
+        Ok(())
+    }
+
+    /// Fail if the options can't be applied.
+    pub(crate) fn check<E: Error>(&self) -> Result<(), E> {
+        match self.max_depth {
+            Some(0) => Err(E::custom("maximum depth must be at least 1")),
+            _ => Ok(()),
+        }
+    }
+
+    /// Return whether maps and sequences found at the given path must be JSON encoded.
+    pub(crate) fn is_too_deep(&self, path: &[String]) -> bool {
+        !path.is_empty() && self.max_depth.is_some_and(|max_depth| path.len() >= max_depth)
+    }
+
+    /// Return whether sequences are flattened as one key per item.
+    pub(crate) fn indexes_arrays(&self) -> bool {
+        self.array_mode == ArrayMode::Indexed
+    }
+
+    /// Build the key of a value found at the given path, `suffix` being its LDP type suffix.
+    pub(crate) fn key(&self, path: &[String], suffix: &str) -> String {
+        let key = self.raw_key(path, suffix);
+        match self.sanitize {
+            true => sanitize_field_name(&key),
+            false => key,
+        }
+    }
+
+    /// Build the key of a value found at the given path, before sanitizing.
+    fn raw_key(&self, path: &[String], suffix: &str) -> String {
+        let mut key = format!("{}{}", self.prefix, path.join(&self.separator));
+        if self.ldp_suffixes {
+            key.push_str(suffix);
+        }
+        key
+    }
+}
+
+/// Flattened fields, along with the path of each one when sanitizing to report collisions.
+struct Flat<'a> {
+    fields: &'a mut BTreeMap<Value, Value>,
+    sources: HashMap<String, Vec<String>>,
+}
+
+/// Return the LDP suffix forcing the type of a value, see the
+/// [`field naming conventions`](https://docs.ovh.com/gb/en/logs-data-platform/field-naming-conventions/).
+///
+/// Unsigned integers are mapped to `_double` and every float to `_float`, as the
+/// `serde-value-utils` schema used before did.
+pub(crate) fn type_suffix(value: &Value) -> &'static str {
+    match value {
+        Value::Bool(_) => "_bool",
+        Value::U8(_) | Value::U16(_) | Value::U32(_) | Value::U64(_) => "_double",
+        Value::I8(_) | Value::I16(_) | Value::I32(_) | Value::I64(_) => "_long",
+        Value::F32(_) | Value::F64(_) => "_float",
+        _ => "",
+    }
+}
+
+/// Convert a map key into a key segment.
+pub(crate) fn key_to_string(key: &Value) -> Result<String, SerializerError> {
+    match key {
+        Value::String(_) | Value::Char(_) | Value::Bool(_) => value_to_string(key),
+        Value::U8(_) | Value::U16(_) | Value::U32(_) | Value::U64(_) => value_to_string(key),
+        Value::I8(_) | Value::I16(_) | Value::I32(_) | Value::I64(_) => value_to_string(key),
+        Value::Newtype(inner) => key_to_string(inner),
+        other => Err(SerializerError::custom(format!("map keys must be strings, got {:?}", other))),
+    }
+}
+
+fn value_to_string(value: &Value) -> Result<String, SerializerError> {
+    Ok(match value {
+        Value::String(data) => data.clone(),
+        Value::Char(data) => data.to_string(),
+        Value::Bool(data) => data.to_string(),
+        Value::U8(data) => data.to_string(),
+        Value::U16(data) => data.to_string(),
+        Value::U32(data) => data.to_string(),
+        Value::U64(data) => data.to_string(),
+        Value::I8(data) => data.to_string(),
+        Value::I16(data) => data.to_string(),
+        Value::I32(data) => data.to_string(),
+        Value::I64(data) => data.to_string(),
+        Value::F32(data) => data.to_string(),
+        Value::F64(data) => data.to_string(),
+        Value::Unit | Value::Option(None) => String::new(),
+        Value::Option(Some(inner)) | Value::Newtype(inner) => value_to_string(inner)?,
+        Value::Bytes(_) | Value::Map(_) | Value::Seq(_) => to_json(value)?,
+    })
+}
+
+fn to_json(value: &Value) -> Result<String, SerializerError> {
+    serde_json::to_string(value).map_err(SerializerError::custom)
+}
@@ -0,0 +1,56 @@
+// Copyright 2019-present, OVH SAS
+// All rights reserved.
+//
+// This OVH Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use std::env;
+use std::sync::RwLock;
+
+/// Environment variables checked, in order, to resolve the default host before falling back to
+/// the system hostname. `POD_NAME` is usually exposed to Kubernetes containers using the
+/// downward API.
+pub const HOST_ENV_VARS: &[&str] = &["GELF_HOST", "POD_NAME"];
+
+static DEFAULT_HOST: RwLock<Option<String>> = RwLock::new(None);
+
+/// Return the host set on new records.
+///
+/// It is resolved once per process from the [`HOST_ENV_VARS`](constant.HOST_ENV_VARS.html)
+/// environment variables, then the system hostname, unless overridden using
+/// [`set_default_host`](fn.set_default_host.html).
+pub fn default_host() -> String {
+    if let Some(ref host) = *DEFAULT_HOST.read().unwrap_or_else(|poisoned| poisoned.into_inner()) {
+        return host.clone();
+    }
+    let resolved = resolve_host();
+    DEFAULT_HOST.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get_or_insert(resolved)
+        .clone()
+}
+
+/// Override the host set on new records.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_gelf::{GelfRecord, GelfRecordBuilder, GelfRecordGetter};
+///
+/// serde_gelf::set_default_host("api-7f9c5d");
+/// assert_eq!(GelfRecord::new().host(), "api-7f9c5d");
+/// ```
+pub fn set_default_host<S: Into<String>>(host: S) {
+    *DEFAULT_HOST.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(host.into());
+}
+
+fn resolve_host() -> String {
+    HOST_ENV_VARS.iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|host| !host.is_empty())
+        .or_else(hostname::get_hostname)
+        .unwrap_or_else(|| "localhost".to_string())
+}
@@ -0,0 +1,240 @@
+// Copyright 2019-present, OVH SAS
+// All rights reserved.
+//
+// This OVH Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde_value::Value;
+use tracing::{Event, Subscriber};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::{ExtensionsMut, LookupSpan};
+
+use crate::level::GelfLevel;
+use crate::record::{GelfRecord, GelfRecordBuilder};
+use crate::sender::GelfSink;
+
+/// A [`tracing_subscriber::Layer`](https://docs.rs/tracing-subscriber/0.3/tracing_subscriber/layer/trait.Layer.html)
+/// which sends every event as a [`GelfRecord`](struct.GelfRecord.html) to a
+/// [`GelfSink`](trait.GelfSink.html).
+///
+/// The fields of the event and of its enclosing spans are flattened into the additional fields,
+/// inner spans overriding outer ones, and the span names are recorded as a `_span` path such as
+/// `request:db_query`. If the event or a span has a field named `span`, it is kept and the path is
+/// recorded as `_span_path` instead.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate tracing;
+/// extern crate tracing_subscriber;
+/// extern crate serde_gelf;
+///
+/// use std::io;
+/// use std::sync::{Arc, Mutex};
+/// use serde_gelf::{GelfLayer, GelfRecord, GelfRecordGetter, GelfSink};
+/// use serde_value::Value;
+/// use tracing_subscriber::prelude::*;
+///
+/// #[derive(Clone, Default)]
+/// struct Capture(Arc<Mutex<Vec<GelfRecord>>>);
+///
+/// impl GelfSink for Capture {
+///     fn send(&self, record: &GelfRecord) -> io::Result<()> {
+///         self.0.lock().unwrap().push(record.clone());
+///         Ok(())
+///     }
+/// }
+///
+/// fn field(record: &GelfRecord, name: &str) -> Option<Value> {
+///     record.additional_fields().get(&Value::String(name.into())).cloned()
+/// }
+///
+/// fn main() {
+///     let capture = Capture::default();
+///     let subscriber = tracing_subscriber::registry().with(GelfLayer::new(capture.clone()));
+///     tracing::subscriber::with_default(subscriber, || {
+///         let request = info_span!("request", user_id = 5, status = "pending");
+///         let _request = request.enter();
+///         let query = info_span!("db_query", table = "users");
+///         let _query = query.enter();
+///         info!(status = 200, "request handled");
+///         warn!(span = "mine", "field named span");
+///     });
+///
+///     let records = capture.0.lock().unwrap();
+///     assert_eq!(records[0].message(), "request handled");
+///     assert_eq!(field(&records[0], "_span"), Some(Value::String("request:db_query".into())));
+///     assert_eq!(field(&records[0], "_user_id"), Some(Value::I64(5)));
+///     assert_eq!(field(&records[0], "_table"), Some(Value::String("users".into())));
+///     // Event fields override span fields.
+///     assert_eq!(field(&records[0], "_status"), Some(Value::I64(200)));
+///
+///     assert_eq!(field(&records[1], "_span"), Some(Value::String("mine".into())));
+///     assert_eq!(field(&records[1], "_span_path"), Some(Value::String("request:db_query".into())));
+/// }
+/// ```
+pub struct GelfLayer<S> {
+    sink: S,
+}
+
+impl<S: GelfSink> GelfLayer<S> {
+    /// Create a layer sending events to the given sink.
+    ///
+    /// Several layers can be stacked, i.e. to send the same events to two sinks.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use]
+    /// extern crate tracing;
+    /// extern crate tracing_subscriber;
+    /// extern crate serde_gelf;
+    ///
+    /// use std::io;
+    /// use std::sync::{Arc, Mutex};
+    /// use serde_gelf::{GelfLayer, GelfRecord, GelfRecordGetter, GelfSink};
+    /// use serde_value::Value;
+    /// use tracing_subscriber::prelude::*;
+    ///
+    /// #[derive(Clone, Default)]
+    /// struct Capture(Arc<Mutex<Vec<GelfRecord>>>);
+    ///
+    /// impl GelfSink for Capture {
+    ///     fn send(&self, record: &GelfRecord) -> io::Result<()> {
+    ///         self.0.lock().unwrap().push(record.clone());
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let (first, second) = (Capture::default(), Capture::default());
+    ///     let subscriber = tracing_subscriber::registry()
+    ///         .with(GelfLayer::new(first.clone()))
+    ///         .with(GelfLayer::new(second.clone()));
+    ///     tracing::subscriber::with_default(subscriber, || {
+    ///         let request = info_span!("request", user_id = 5, status = tracing::field::Empty);
+    ///         let _request = request.enter();
+    ///         request.record("status", 200);
+    ///         info!("request handled");
+    ///     });
+    ///
+    ///     for capture in &[first, second] {
+    ///         let records = capture.0.lock().unwrap();
+    ///         let fields = records[0].additional_fields();
+    ///         assert_eq!(fields.get(&Value::String("_user_id".into())), Some(&Value::I64(5)));
+    ///         assert_eq!(fields.get(&Value::String("_status".into())), Some(&Value::I64(200)));
+    ///     }
+    /// }
+    /// ```
+    pub fn new(sink: S) -> GelfLayer<S> {
+        GelfLayer { sink }
+    }
+}
+
+/// Fields recorded on a span, stored in the span extensions.
+struct SpanFields(BTreeMap<Value, Value>);
+
+/// Collect the fields of a span or event, the `message` field apart.
+struct FieldVisitor<'a> {
+    fields: &'a mut BTreeMap<Value, Value>,
+    message: Option<String>,
+}
+
+impl<'a> FieldVisitor<'a> {
+    fn new(fields: &'a mut BTreeMap<Value, Value>) -> FieldVisitor<'a> {
+        FieldVisitor { fields, message: None }
+    }
+
+    fn insert(&mut self, field: &Field, value: Value) {
+        self.fields.insert(Value::String(field.name().to_string()), value);
+    }
+}
+
+impl<'a> Visit for FieldVisitor<'a> {
+    fn record_f64(&mut self, field: &Field, value: f64) { self.insert(field, Value::F64(value)) }
+    fn record_i64(&mut self, field: &Field, value: i64) { self.insert(field, Value::I64(value)) }
+    fn record_u64(&mut self, field: &Field, value: u64) { self.insert(field, Value::U64(value)) }
+    fn record_bool(&mut self, field: &Field, value: bool) { self.insert(field, Value::Bool(value)) }
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "message" => self.message = Some(value.to_string()),
+            _ => self.insert(field, Value::String(value.to_string())),
+        }
+    }
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        match field.name() {
+            "message" => self.message = Some(format!("{:?}", value)),
+            _ => self.insert(field, Value::String(format!("{:?}", value))),
+        }
+    }
+}
+
+/// Merge fields into those recorded on a span, which are shared by every stacked `GelfLayer`.
+fn merge_span_fields(extensions: &mut ExtensionsMut<'_>, fields: BTreeMap<Value, Value>) {
+    match extensions.get_mut::<SpanFields>() {
+        Some(SpanFields(recorded)) => recorded.extend(fields),
+        None => extensions.insert(SpanFields(fields)),
+    }
+}
+
+impl<S, Sub> Layer<Sub> for GelfLayer<S>
+    where S: GelfSink + 'static, Sub: Subscriber + for<'a> LookupSpan<'a> {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, Sub>) {
+        if let Some(span) = ctx.span(id) {
+            let mut fields = BTreeMap::new();
+            attrs.record(&mut FieldVisitor::new(&mut fields));
+            merge_span_fields(&mut span.extensions_mut(), fields);
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, Sub>) {
+        if let Some(span) = ctx.span(id) {
+            let mut fields = BTreeMap::new();
+            values.record(&mut FieldVisitor::new(&mut fields));
+            merge_span_fields(&mut span.extensions_mut(), fields);
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, Sub>) {
+        let mut fields = BTreeMap::new();
+        let mut path = Vec::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                path.push(span.name());
+                if let Some(SpanFields(span_fields)) = span.extensions().get::<SpanFields>() {
+                    fields.extend(span_fields.clone());
+                }
+            }
+        }
+        let mut visitor = FieldVisitor::new(&mut fields);
+        event.record(&mut visitor);
+        let message = visitor.message.unwrap_or_default();
+
+        let span_key = if fields.contains_key(&Value::String("span".into())) { "_span_path" } else { "_span" };
+
+        let metadata = event.metadata();
+        let mut record = GelfRecord::new()
+            .set_facility(metadata.target().to_string())
+            .set_file(metadata.file().unwrap_or("").to_string())
+            .set_line(metadata.line().unwrap_or(0))
+            .set_level(GelfLevel::from(*metadata.level()))
+            .set_message(message)
+            .add_additional_fields(fields);
+        if !path.is_empty() {
+            let mut span = BTreeMap::new();
+            span.insert(Value::String(span_key.into()), Value::String(path.join(":")));
+            record = record.extend_additional_fields(span);
+        }
+        let _ = self.sink.send(&record);
+    }
+}
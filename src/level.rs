@@ -54,7 +54,6 @@ impl Default for GelfLevel {
     }
 }
 
-
 impl From<Level> for GelfLevel {
     fn from(level: Level) -> GelfLevel {
         match level {
@@ -67,6 +66,33 @@ impl From<Level> for GelfLevel {
     }
 }
 
+#[cfg(feature = "tracing")]
+impl From<tracing::Level> for GelfLevel {
+    fn from(level: tracing::Level) -> GelfLevel {
+        match level {
+            tracing::Level::TRACE => GelfLevel::Debugging,
+            tracing::Level::DEBUG => GelfLevel::Debugging,
+            tracing::Level::INFO => GelfLevel::Informational,
+            tracing::Level::WARN => GelfLevel::Warning,
+            tracing::Level::ERROR => GelfLevel::Error,
+        }
+    }
+}
+
+#[cfg(feature = "slog")]
+impl From<slog::Level> for GelfLevel {
+    fn from(level: slog::Level) -> GelfLevel {
+        match level {
+            slog::Level::Trace => GelfLevel::Debugging,
+            slog::Level::Debug => GelfLevel::Debugging,
+            slog::Level::Info => GelfLevel::Informational,
+            slog::Level::Warning => GelfLevel::Warning,
+            slog::Level::Error => GelfLevel::Error,
+            slog::Level::Critical => GelfLevel::Critical,
+        }
+    }
+}
+
 impl From<&GelfLevel> for Level {
     fn from(level: &GelfLevel) -> Level {
         match level {
@@ -94,7 +120,6 @@ impl From<u32> for GelfLevel {
     }
 }
 
-
 impl From<GelfLevel> for String {
     fn from(level: GelfLevel) -> String {
         match level {
@@ -66,22 +66,84 @@ html_logo_url = "https://eu.api.ovh.com/images/com-square-bichro.png",
 html_favicon_url = "https://www.ovh.com/favicon.ico",
 )]
 #![deny(warnings, missing_docs)]
+#[cfg(feature = "chrono")]
+extern crate chrono;
+#[cfg(feature = "compression")]
+extern crate flate2;
 extern crate log;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
+#[cfg(feature = "derive")]
+extern crate serde_gelf_derive;
+extern crate serde_json;
 extern crate serde_value;
-extern crate serde_value_utils;
+#[cfg(feature = "slog")]
+extern crate slog;
+#[cfg(feature = "time")]
+extern crate time;
+#[cfg(feature = "tls")]
+extern crate rustls;
+#[cfg(feature = "tracing")]
+extern crate tracing;
+#[cfg(feature = "tracing")]
+extern crate tracing_subscriber;
 
+#[cfg(feature = "compression")]
+pub use compression::Compression;
+#[cfg(feature = "slog")]
+pub use drain::GelfDrain;
+#[cfg(feature = "tracing")]
+pub use layer::GelfLayer;
+pub use error::Error;
+pub use flatten::{ArrayMode, FlattenOptions};
+pub use host::{default_host, set_default_host, HOST_ENV_VARS};
 pub use level::GelfLevel;
 pub use record::{GelfRecord, GelfRecordBuilder, GelfRecordGetter, GelfRecordSetter};
+pub use receiver::{TcpReceiver, TcpStopHandle, UdpIncoming, UdpReceiver};
+pub use record_ref::GelfRecordRef;
+#[cfg(feature = "derive")]
+pub use serde_gelf_derive::GelfRecord;
+pub use logger::GelfLogger;
+pub use validation::{is_valid_field_name, sanitize_field_name};
+pub use timestamp::TimestampPrecision;
+#[cfg(feature = "ovh-ldp-sender")]
+pub use sender::{LdpOptions, LdpSender, LdpTransport, LDP_TOKEN_FIELD};
+#[cfg(feature = "tls")]
+pub use sender::TlsOptions;
+pub use sender::{emit, global_enabled, global_max_level, set_global_max_level, set_global_sink, take_global_sink, AsyncGuard, AsyncSink, FileSink, GelfSink, HttpSender, OverflowPolicy, StdoutSink, TcpSender, UdpSender};
 
+mod chunk;
+#[cfg(feature = "compression")]
+mod compression;
+mod error;
+mod flatten;
+mod host;
+mod receiver;
 mod record;
+mod record_ref;
+mod ser;
+#[cfg(feature = "slog")]
+mod drain;
+#[cfg(feature = "tracing")]
+mod layer;
 mod level;
+mod logger;
+mod sender;
+mod timestamp;
+mod validation;
 
 #[macro_use]
 mod macros;
 
+/// Items used by the generated code, not part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use serde_value::{to_value, Value};
+    pub use crate::record::flatten_fields;
+    pub use crate::timestamp::AsTimestamp;
+}
+
 /// Transform any serializable object into a single level hashmap of key / value.
 ///
 /// # Examples
@@ -112,6 +174,201 @@ mod macros;
 /// ```text
 /// {"_a": U32(15), "_b_c": Bool(true), "_b_d": String("hello")}
 /// ```
-pub fn to_flat_dict<S: ?Sized>(value: &S) -> Result<std::collections::BTreeMap<serde_value::Value, serde_value::Value>, serde_value::SerializerError> where S: serde::Serialize {
-    serde_value_utils::to_flatten_maptree("_", Some("_"), value)
-}
\ No newline at end of file
+pub fn to_flat_dict<S>(value: &S) -> Result<std::collections::BTreeMap<serde_value::Value, serde_value::Value>, serde_value::SerializerError> where S: serde::Serialize + ?Sized {
+    to_flat_dict_with(value, &FlattenOptions::default())
+}
+
+/// Transform any serializable object into a single level hashmap of key / value, using the given
+/// [`FlattenOptions`](struct.FlattenOptions.html).
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serde_gelf;
+///
+/// use serde_gelf::{ArrayMode, FlattenOptions};
+///
+/// #[derive(Serialize)]
+/// struct SubFoo {
+///     c: Vec<bool>,
+///     d: String,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct Foo {
+///     a: u32,
+///     b: SubFoo,
+/// }
+///
+/// fn main() {
+///     let foo = Foo { a: 15, b: SubFoo { c: vec![true, false], d: "hello".into() }};
+///     let options = FlattenOptions::new()
+///         .set_separator(".")
+///         .set_array_mode(ArrayMode::Joined(",".into()));
+///     println!("{:?}", serde_gelf::to_flat_dict_with(&foo, &options).unwrap());
+/// }
+/// ```
+/// **Output**:
+/// ```text
+/// {"_a": U32(15), "_b.c": String("true,false"), "_b.d": String("hello")}
+/// ```
+pub fn to_flat_dict_with<S>(value: &S, options: &FlattenOptions) -> Result<std::collections::BTreeMap<serde_value::Value, serde_value::Value>, serde_value::SerializerError> where S: serde::Serialize + ?Sized {
+    options.flatten(&serde_value::to_value(value)?)
+}
+
+/// Same as [`to_flat_dict`](fn.to_flat_dict.html), rewriting the keys which are not valid
+/// additional field names, see [`sanitize_field_name`](fn.sanitize_field_name.html).
+///
+/// # Examples
+///
+/// ```rust
+/// use std::collections::BTreeMap;
+///
+/// let mut extra = BTreeMap::new();
+/// extra.insert("user name", "John");
+/// extra.insert("id", "42");
+///
+/// let flat = serde_gelf::to_sanitized_flat_dict(&extra).unwrap();
+/// assert!(flat.contains_key(&serde_value::Value::String("_user_name".into())));
+/// assert!(flat.contains_key(&serde_value::Value::String("__id".into())));
+/// ```
+pub fn to_sanitized_flat_dict<S>(value: &S) -> Result<std::collections::BTreeMap<serde_value::Value, serde_value::Value>, serde_value::SerializerError> where S: serde::Serialize + ?Sized {
+    to_flat_dict_with(value, &FlattenOptions::default().set_sanitize(true))
+}
+
+/// Write the flattened representation of a value as a JSON object, without building the
+/// intermediate `serde_value::Value` trees used by [`to_flat_dict`](fn.to_flat_dict.html).
+///
+/// Entries are the same as [`to_flat_dict`](fn.to_flat_dict.html) ones but are written in the
+/// serialization order of the value instead of being sorted. As entries are not collected, two
+/// values flattened into the same key, which [`to_flat_dict`](fn.to_flat_dict.html) merges,
+/// make the serialization fail instead of writing duplicate JSON keys.
+///
+/// # Examples
+///
+/// ```rust
+/// extern crate serde_gelf;
+/// #[macro_use]
+/// extern crate serde_derive;
+///
+/// #[derive(Serialize)]
+/// struct SubFoo {
+///     c: bool,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct Foo {
+///     a: String,
+///     b: SubFoo,
+/// }
+///
+/// fn main() {
+///     let foo = Foo { a: "hello".into(), b: SubFoo { c: true } };
+///     let mut payload = Vec::new();
+///     serde_gelf::to_flat_writer(&mut payload, &foo).unwrap();
+///     assert_eq!(serde_json::to_value(&serde_gelf::to_flat_dict(&foo).unwrap()).unwrap(), serde_json::from_slice::<serde_json::Value>(&payload).unwrap());
+/// }
+/// ```
+pub fn to_flat_writer<W, S>(writer: W, value: &S) -> Result<(), Error> where W: std::io::Write, S: serde::Serialize + ?Sized {
+    to_flat_writer_with(writer, value, &FlattenOptions::default())
+}
+
+/// Same as [`to_flat_writer`](fn.to_flat_writer.html), using the given
+/// [`FlattenOptions`](struct.FlattenOptions.html).
+pub fn to_flat_writer_with<W, S>(writer: W, value: &S, options: &FlattenOptions) -> Result<(), Error> where W: std::io::Write, S: serde::Serialize + ?Sized {
+    use serde::ser::{SerializeMap, Serializer};
+
+    let mut serializer = serde_json::Serializer::new(writer);
+    let mut map = serializer.serialize_map(None)?;
+    ser::serialize_flat_entries(&mut map, value, options, Default::default())?;
+    map.end()?;
+    Ok(())
+}
+
+/// Same as [`to_flat_writer`](fn.to_flat_writer.html), returning the JSON object as a byte
+/// vector.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::collections::BTreeMap;
+///
+/// let mut extra = BTreeMap::new();
+/// extra.insert("user", vec!["John", "Jane"]);
+///
+/// assert_eq!(serde_gelf::to_flat_vec(&extra).unwrap(), br#"{"_user_0":"John","_user_1":"Jane"}"#.to_vec());
+///
+/// // `a.b` and `a_b` are both flattened into `_a_b`.
+/// assert!(serde_gelf::to_flat_vec(&serde_json::json!({"a": {"b": "x"}, "a_b": "y"})).is_err());
+/// ```
+pub fn to_flat_vec<S>(value: &S) -> Result<Vec<u8>, Error> where S: serde::Serialize + ?Sized {
+    let mut payload = Vec::with_capacity(128);
+    to_flat_writer(&mut payload, value)?;
+    Ok(payload)
+}
+
+/// Parse a GELF JSON payload into a [`GelfRecord`](struct.GelfRecord.html).
+///
+/// Every key prefixed with an underscore is stored as an additional field, any other unknown key
+/// is rejected according to the
+/// [`GELF Payload Specification`](http://docs.graylog.org/en/3.0/pages/gelf.html#gelf-payload-specification).
+///
+/// # Examples
+///
+/// ```rust
+/// extern crate serde_gelf;
+///
+/// use serde_gelf::{GelfLevel, GelfRecordGetter};
+///
+/// fn main() {
+///     let rec = serde_gelf::from_str(r#"{
+///         "version": "1.1",
+///         "host": "example.org",
+///         "short_message": "A short message",
+///         "level": 5,
+///         "_user_id": 9001
+///     }"#).unwrap();
+///
+///     assert_eq!(rec.message(), "A short message");
+///     assert_eq!(rec.level(), GelfLevel::Notice);
+///     assert_eq!(rec.additional_fields().len(), 1);
+/// }
+/// ```
+///
+/// The `version`, `host` and `short_message` fields are mandatory, unknown keys without an
+/// underscore prefix, duplicate keys and levels above 7 are rejected:
+///
+/// ```rust
+/// let missing = |field: &str| {
+///     let mut payload = serde_json::json!({"version": "1.1", "host": "example.org", "short_message": "hi"});
+///     payload.as_object_mut().unwrap().remove(field);
+///     serde_gelf::from_str(&payload.to_string()).unwrap_err().to_string()
+/// };
+/// assert!(missing("version").starts_with("missing field `version`"));
+/// assert!(missing("host").starts_with("missing field `host`"));
+/// assert!(missing("short_message").starts_with("missing field `short_message`"));
+///
+/// let err = serde_gelf::from_str(r#"{"version": "1.1", "host": "example.org", "short_message": "hi", "user_id": 1}"#).unwrap_err();
+/// assert!(err.to_string().starts_with("unknown field `user_id`"));
+///
+/// let err = serde_gelf::from_str(r#"{"version": "1.1", "host": "a", "host": "b", "short_message": "hi"}"#).unwrap_err();
+/// assert!(err.to_string().starts_with("duplicate field `host`"));
+///
+/// let err = serde_gelf::from_str(r#"{"version": "1.1", "host": "a", "short_message": "hi", "_id": 1, "_id": 2}"#).unwrap_err();
+/// assert!(err.to_string().starts_with("duplicate field `_id`"));
+///
+/// let err = serde_gelf::from_str(r#"{"version": "1.1", "host": "a", "short_message": "hi", "level": 42}"#).unwrap_err();
+/// assert!(err.to_string().starts_with("invalid value: integer `42`, expected a syslog level between 0 and 7"));
+/// ```
+pub fn from_str(s: &str) -> serde_json::Result<GelfRecord> {
+    serde_json::from_str(s)
+}
+
+/// Parse a GELF JSON payload from a byte slice into a [`GelfRecord`](struct.GelfRecord.html).
+///
+/// See [`from_str`](fn.from_str.html) for details.
+pub fn from_slice(v: &[u8]) -> serde_json::Result<GelfRecord> {
+    serde_json::from_slice(v)
+}
@@ -0,0 +1,107 @@
+// Copyright 2019-present, OVH SAS
+// All rights reserved.
+//
+// This OVH Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use std::collections::BTreeMap;
+
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use serde_value::Value;
+
+use crate::flatten::FlattenOptions;
+use crate::record::{flatten_fields, GelfRecord, GelfRecordBuilder};
+use crate::sender::GelfSink;
+
+/// A [`log::Log`](https://docs.rs/log/0.4/log/trait.Log.html) implementation which sends every
+/// record as a [`GelfRecord`](struct.GelfRecord.html) to a [`GelfSink`](trait.GelfSink.html).
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate log;
+/// extern crate serde_gelf;
+///
+/// use log::LevelFilter;
+/// use serde_gelf::{GelfLogger, StdoutSink};
+///
+/// fn main() {
+///     GelfLogger::new(StdoutSink::new()).set_level(LevelFilter::Info).init().unwrap();
+///
+///     info!("hello");
+/// }
+/// ```
+pub struct GelfLogger {
+    sink: Box<dyn GelfSink>,
+    level: LevelFilter,
+    additional_fields: BTreeMap<Value, Value>,
+}
+
+impl GelfLogger {
+    /// Create a logger sending records to the given sink, every level is enabled by default.
+    pub fn new<S: GelfSink + 'static>(sink: S) -> GelfLogger {
+        GelfLogger {
+            sink: Box::new(sink),
+            level: LevelFilter::Trace,
+            additional_fields: BTreeMap::new(),
+        }
+    }
+
+    /// Set the maximum level of the records to send.
+    pub fn set_level(mut self, level: LevelFilter) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Extend a non-flatten dict to the additional fields added to every record.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::collections::BTreeMap;
+    /// use serde_gelf::{GelfLogger, StdoutSink};
+    ///
+    /// let mut extra = BTreeMap::new();
+    /// extra.insert(serde_value::Value::String("app".into()), serde_value::Value::String("api".into()));
+    ///
+    /// let logger = GelfLogger::new(StdoutSink::new())
+    ///     .add_additional_fields(extra);
+    /// ```
+    pub fn add_additional_fields(mut self, additional_fields: BTreeMap<Value, Value>) -> Self {
+        if let Ok(flat) = flatten_fields(&additional_fields, &FlattenOptions::default()) {
+            self.additional_fields.extend(flat);
+        }
+        self
+    }
+
+    /// Install the logger as the global logger and set the `log` max level accordingly.
+    pub fn init(self) -> Result<(), SetLoggerError> {
+        let level = self.level;
+        log::set_logger(Box::leak(Box::new(self)))?;
+        log::set_max_level(level);
+        Ok(())
+    }
+}
+
+impl Log for GelfLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let record = GelfRecord::from(record).extend_additional_fields(self.additional_fields.clone());
+        let _ = self.sink.send(&record);
+    }
+
+    fn flush(&self) {
+        let _ = self.sink.flush();
+    }
+}
@@ -11,6 +11,12 @@
 /// Construct a [`GelfRecord`](struct.GelfRecord.html), a struct which follow the
 /// [`GELF Payload Specification`](http://docs.graylog.org/en/3.0/pages/gelf.html#gelf-payload-specification).
 ///
+/// Leading `key = value` pairs, separated from the message by a `;` or a `,`, are added as
+/// additional fields: each value may be any `Serialize` type and is flattened using the same
+/// rules as [`to_flat_dict`](fn.to_flat_dict.html), dotted keys being nested. Fields which cannot
+/// be flattened are left out, see [`gelf_try_record!`](macro.gelf_try_record.html) to catch the
+/// error.
+///
 /// # Examples
 ///
 /// ```rust
@@ -37,10 +43,35 @@
 ///   "version": "1.1"
 /// }
 /// ```
+///
+/// With additional fields:
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate serde_gelf;
+///
+/// use serde_gelf::{GelfLevel, GelfRecordGetter};
+/// use serde_value::Value;
+///
+/// fn main() {
+///     let rec = gelf_record!(level: GelfLevel::Notice, user = "John", request.path = "/login"; "{} logged in", "John");
+///     let fields = rec.additional_fields();
+///     assert_eq!(fields.get(&Value::String("_user".into())), Some(&Value::String("John".into())));
+///     assert_eq!(fields.get(&Value::String("_request_path".into())), Some(&Value::String("/login".into())));
+/// }
+/// ```
 #[macro_export]
 macro_rules! gelf_record {
-    (level: $level:expr, extra: $extra:expr, $($arg:tt)+ ) => {{
-        use std::collections::BTreeMap;
+    (@fields $level:expr, [$($fields:tt)*], $($key:ident).+ = $value:expr, $($rest:tt)+) => {
+        $crate::gelf_record!(@fields $level, [$($fields)* {[$($key),+] $value}], $($rest)+)
+    };
+    (@fields $level:expr, [$($fields:tt)*], $($key:ident).+ = $value:expr; $($arg:tt)+) => {
+        $crate::gelf_record!(@build $level, [$($fields)* {[$($key),+] $value}], $($arg)+)
+    };
+    (@fields $level:expr, [$($fields:tt)*], $($arg:tt)+) => {
+        $crate::gelf_record!(@build $level, [$($fields)*], $($arg)+)
+    };
+    (@build $level:expr, [$({[$($key:ident),+] $value:expr})*], $($arg:tt)+) => {{
         use $crate::GelfRecordBuilder;
 
         $crate::GelfRecord::new()
@@ -49,9 +80,159 @@ macro_rules! gelf_record {
             .set_line(line!())
             .set_level($level)
             .set_message(format_args!($($arg)+).to_string())
-            .add_additional_fields($extra.clone())
+            $(.add_nested_additional_field(&[$(stringify!($key)),+], &$value))*
+    }};
+    (level: $level:expr, extra: $extra:expr, $($arg:tt)+ ) => {{
+        use $crate::GelfRecordBuilder;
+
+        $crate::gelf_record!(@build $level, [], $($arg)+)
+            .extend_additional_fields($crate::__private::flatten_fields(&$extra, &$crate::FlattenOptions::default()).unwrap_or_default())
+    }};
+    (level: $level:expr, $($arg:tt)+ ) => {$crate::gelf_record!(@fields $level, [], $($arg)+)};
+    (extra: $extra:expr, $($arg:tt)+ ) => {$crate::gelf_record!(level: $crate::GelfLevel::default(), extra: $extra, $($arg)+)};
+    ($($arg:tt)+) => {$crate::gelf_record!(@fields $crate::GelfLevel::default(), [], $($arg)+)};
+}
+
+/// Same as [`gelf_record!`](macro.gelf_record.html), returning an [`Error`](enum.Error.html)
+/// if an additional field cannot be flattened.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate serde_gelf;
+///
+/// use std::collections::BTreeMap;
+///
+/// fn main() {
+///     assert!(gelf_try_record!(user = "John"; "{} logged in", "John").is_ok());
+///
+///     // Map keys must be strings.
+///     let mut ids = BTreeMap::new();
+///     ids.insert(vec![1], "John");
+///     assert!(gelf_try_record!(ids = ids; "hello").is_err());
+///     assert!(gelf_try_record!(extra: &ids, "hello").is_err());
+/// }
+/// ```
+#[macro_export]
+macro_rules! gelf_try_record {
+    (@fields $level:expr, [$($fields:tt)*], $($key:ident).+ = $value:expr, $($rest:tt)+) => {
+        $crate::gelf_try_record!(@fields $level, [$($fields)* {[$($key),+] $value}], $($rest)+)
+    };
+    (@fields $level:expr, [$($fields:tt)*], $($key:ident).+ = $value:expr; $($arg:tt)+) => {
+        $crate::gelf_try_record!(@build $level, [$($fields)* {[$($key),+] $value}], $($arg)+)
+    };
+    (@fields $level:expr, [$($fields:tt)*], $($arg:tt)+) => {
+        $crate::gelf_try_record!(@build $level, [$($fields)*], $($arg)+)
+    };
+    (@build $level:expr, [$({[$($key:ident),+] $value:expr})*], $($arg:tt)+) => {{
+        use $crate::GelfRecordBuilder;
+
+        let record: ::std::result::Result<$crate::GelfRecord, $crate::Error> = ::std::result::Result::Ok(
+            $crate::gelf_record!(@build $level, [], $($arg)+)
+        );
+        $(let record = record.and_then(|record| record.try_add_nested_additional_field(&[$(stringify!($key)),+], &$value));)*
+        record
+    }};
+    (level: $level:expr, extra: $extra:expr, $($arg:tt)+ ) => {{
+        use $crate::GelfRecordBuilder;
+
+        $crate::__private::flatten_fields(&$extra, &$crate::FlattenOptions::default())
+            .map_err($crate::Error::from)
+            .map(|fields| $crate::gelf_record!(@build $level, [], $($arg)+).extend_additional_fields(fields))
+    }};
+    (level: $level:expr, $($arg:tt)+ ) => {$crate::gelf_try_record!(@fields $level, [], $($arg)+)};
+    (extra: $extra:expr, $($arg:tt)+ ) => {$crate::gelf_try_record!(level: $crate::GelfLevel::default(), extra: $extra, $($arg)+)};
+    ($($arg:tt)+) => {$crate::gelf_try_record!(@fields $crate::GelfLevel::default(), [], $($arg)+)};
+}
+
+/// Construct a [`GelfRecord`](struct.GelfRecord.html) with the given level and send it to the
+/// global sink, see [`set_global_sink`](fn.set_global_sink.html).
+///
+/// Leading `key = value` pairs are added as additional fields, see
+/// [`gelf_record!`](macro.gelf_record.html). The record is only built if a global sink is
+/// registered and the level is enabled, see [`set_global_max_level`](fn.set_global_max_level.html).
+/// Send errors are ignored, use [`emit`](fn.emit.html) to handle them.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate serde_gelf;
+///
+/// use serde_gelf::GelfLevel;
+///
+/// fn main() {
+///     let name = "John";
+///     gelf_log!(level: GelfLevel::Notice, user_id = 5, roles = vec!["admin"], "{} logged in", name);
+/// }
+/// ```
+#[macro_export]
+macro_rules! gelf_log {
+    (level: $level:expr, $($arg:tt)+) => {{
+        let level = $level;
+        if $crate::global_enabled(level) {
+            let _ = $crate::emit(&$crate::gelf_record!(level: level, $($arg)+));
+        }
     }};
-    (level: $level:expr, $($arg:tt)+ ) => {gelf_record!(level: $level, extra: &BTreeMap::new(), $($arg)+)};
-    (extra: $extra:expr, $($arg:tt)+ ) => {gelf_record!(level: $crate::GelfLevel::default(), extra: $extra, $($arg)+)};
-    ($($arg:tt)+) => {gelf_record!(level: $crate::GelfLevel::default(), extra: &BTreeMap::new(), $($arg)+)};
+}
+
+/// Send an "Emergency" record to the global sink, see [`gelf_log!`](macro.gelf_log.html).
+#[macro_export]
+macro_rules! gelf_emergency {
+    ($($arg:tt)+) => {$crate::gelf_log!(level: $crate::GelfLevel::Emergency, $($arg)+)};
+}
+
+/// Send an "Alert" record to the global sink, see [`gelf_log!`](macro.gelf_log.html).
+#[macro_export]
+macro_rules! gelf_alert {
+    ($($arg:tt)+) => {$crate::gelf_log!(level: $crate::GelfLevel::Alert, $($arg)+)};
+}
+
+/// Send a "Critical" record to the global sink, see [`gelf_log!`](macro.gelf_log.html).
+#[macro_export]
+macro_rules! gelf_critical {
+    ($($arg:tt)+) => {$crate::gelf_log!(level: $crate::GelfLevel::Critical, $($arg)+)};
+}
+
+/// Send an "Error" record to the global sink, see [`gelf_log!`](macro.gelf_log.html).
+#[macro_export]
+macro_rules! gelf_error {
+    ($($arg:tt)+) => {$crate::gelf_log!(level: $crate::GelfLevel::Error, $($arg)+)};
+}
+
+/// Send a "Warning" record to the global sink, see [`gelf_log!`](macro.gelf_log.html).
+#[macro_export]
+macro_rules! gelf_warn {
+    ($($arg:tt)+) => {$crate::gelf_log!(level: $crate::GelfLevel::Warning, $($arg)+)};
+}
+
+/// Send a "Notice" record to the global sink, see [`gelf_log!`](macro.gelf_log.html).
+#[macro_export]
+macro_rules! gelf_notice {
+    ($($arg:tt)+) => {$crate::gelf_log!(level: $crate::GelfLevel::Notice, $($arg)+)};
+}
+
+/// Send an "Informational" record to the global sink, see [`gelf_log!`](macro.gelf_log.html).
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate serde_gelf;
+///
+/// fn main() {
+///     gelf_info!(user_id = 5, "logged in");
+///     gelf_info!("{} users", 42);
+/// }
+/// ```
+#[macro_export]
+macro_rules! gelf_info {
+    ($($arg:tt)+) => {$crate::gelf_log!(level: $crate::GelfLevel::Informational, $($arg)+)};
+}
+
+/// Send a "Debugging" record to the global sink, see [`gelf_log!`](macro.gelf_log.html).
+#[macro_export]
+macro_rules! gelf_debug {
+    ($($arg:tt)+) => {$crate::gelf_log!(level: $crate::GelfLevel::Debugging, $($arg)+)};
 }
\ No newline at end of file
@@ -0,0 +1,47 @@
+// Copyright 2019-present, OVH SAS
+// All rights reserved.
+//
+// This OVH Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use std::io;
+
+#[cfg(feature = "compression")]
+use crate::compression::Compression;
+use crate::record::GelfRecord;
+
+pub use self::tcp::{TcpReceiver, TcpStopHandle};
+pub use self::udp::{UdpIncoming, UdpReceiver};
+
+mod tcp;
+mod udp;
+
+/// Default maximum size of a decoded payload.
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 8 * 1024 * 1024;
+
+/// Parse a received payload, decompressing it if required.
+///
+/// GZIP and ZLIB payloads are only supported with the `compression` feature.
+fn decode(payload: &[u8], max_size: usize) -> io::Result<GelfRecord> {
+    #[cfg(feature = "compression")]
+    let decompressed;
+    #[cfg(feature = "compression")]
+    let payload = match Compression::detect(payload) {
+        Compression::None => payload,
+        compression => {
+            decompressed = compression.decompress(payload, max_size)?;
+            &decompressed[..]
+        }
+    };
+    if payload.len() > max_size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("payload exceeds {} bytes", max_size),
+        ));
+    }
+    Ok(serde_json::from_slice(payload)?)
+}
@@ -0,0 +1,402 @@
+// Copyright 2019-present, OVH SAS
+// All rights reserved.
+//
+// This OVH Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+use crate::receiver::DEFAULT_MAX_MESSAGE_SIZE;
+use crate::record::GelfRecord;
+
+/// Default time a connection may stay idle before being closed.
+pub const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(300);
+/// Default maximum number of connections served at once.
+pub const DEFAULT_MAX_CONNECTIONS: usize = 1024;
+
+type ErrorHandler = Arc<dyn Fn(Option<SocketAddr>, io::Error) + Send + Sync>;
+
+/// Receive [`GelfRecord`](struct.GelfRecord.html) over TCP.
+///
+/// Streams are split into frames on null bytes, and optionally on newlines. Malformed or
+/// oversized frames are reported to the error handler and skipped, the connection being kept.
+///
+/// Connections idle for longer than the read timeout are closed, as well as the connections
+/// exceeding the maximum number of connections.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate serde_gelf;
+///
+/// use std::sync::mpsc;
+/// use std::thread;
+/// use serde_gelf::{GelfRecordGetter, TcpReceiver, TcpSender};
+///
+/// fn main() {
+///     let receiver = TcpReceiver::bind("127.0.0.1:0").unwrap();
+///     let sender = TcpSender::new(receiver.local_addr().unwrap()).unwrap();
+///
+///     let (records, rx) = mpsc::sync_channel(16);
+///     thread::spawn(move || receiver.serve(move |rec| records.send(rec).unwrap()));
+///
+///     sender.send(&gelf_record!("hello")).unwrap();
+///     assert_eq!(rx.recv().unwrap().message(), "hello");
+/// }
+/// ```
+pub struct TcpReceiver {
+    listener: TcpListener,
+    max_frame_size: usize,
+    newline_delimited: bool,
+    read_timeout: Option<Duration>,
+    max_connections: usize,
+    stopped: Arc<AtomicBool>,
+    on_error: ErrorHandler,
+}
+
+/// Stop a [`TcpReceiver`](struct.TcpReceiver.html), see
+/// [`TcpReceiver::stop_handle`](struct.TcpReceiver.html#method.stop_handle).
+#[derive(Debug, Clone)]
+pub struct TcpStopHandle {
+    addr: SocketAddr,
+    stopped: Arc<AtomicBool>,
+}
+
+impl TcpStopHandle {
+    /// Stop accepting connections and close the open ones, `serve` then returns.
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Wake up the listener blocked on accept.
+        let _ = TcpStream::connect(self.addr);
+    }
+}
+
+/// Open connections, closed when the receiver stops.
+#[derive(Default)]
+struct Connections {
+    next_id: u64,
+    streams: HashMap<u64, TcpStream>,
+}
+
+impl TcpReceiver {
+    /// Create a receiver listening on the given address.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<TcpReceiver> {
+        Ok(TcpReceiver {
+            listener: TcpListener::bind(addr)?,
+            max_frame_size: DEFAULT_MAX_MESSAGE_SIZE,
+            newline_delimited: false,
+            read_timeout: Some(DEFAULT_READ_TIMEOUT),
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            stopped: Arc::new(AtomicBool::new(false)),
+            on_error: Arc::new(|_, _| {}),
+        })
+    }
+
+    /// Return the address the receiver listens on.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Set the maximum size of a frame, larger frames being skipped.
+    pub fn set_max_frame_size(mut self, size: usize) -> Self {
+        self.max_frame_size = size;
+        self
+    }
+
+    /// Also split streams on newlines, a trailing carriage return being ignored.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use]
+    /// extern crate serde_gelf;
+    ///
+    /// use std::io::Write;
+    /// use std::net::TcpStream;
+    /// use std::sync::mpsc;
+    /// use std::thread;
+    /// use serde_gelf::{GelfRecordGetter, TcpReceiver};
+    ///
+    /// fn main() {
+    ///     let (errors, errors_rx) = mpsc::channel();
+    ///     let receiver = TcpReceiver::bind("127.0.0.1:0").unwrap()
+    ///         .set_newline_delimited(true)
+    ///         .set_max_frame_size(1024)
+    ///         .set_error_handler(move |_, err| errors.send(err.kind()).unwrap());
+    ///     let mut client = TcpStream::connect(receiver.local_addr().unwrap()).unwrap();
+    ///
+    ///     let (records, rx) = mpsc::channel();
+    ///     thread::spawn(move || receiver.serve(move |rec| records.send(rec).unwrap()));
+    ///
+    ///     // A malformed frame and an oversized one are skipped, the connection is kept.
+    ///     client.write_all(b"{not json}\r\n").unwrap();
+    ///     client.write_all(&vec![b'x'; 2048]).unwrap();
+    ///     client.write_all(b"\n").unwrap();
+    ///     let record = serde_json::to_string(&gelf_record!("hello")).unwrap();
+    ///     client.write_all(format!("{}\r\n", record).as_bytes()).unwrap();
+    ///
+    ///     assert_eq!(rx.recv().unwrap().message(), "hello");
+    ///     assert_eq!(errors_rx.recv().unwrap(), std::io::ErrorKind::InvalidData);
+    ///     assert_eq!(errors_rx.recv().unwrap(), std::io::ErrorKind::InvalidData);
+    /// }
+    /// ```
+    pub fn set_newline_delimited(mut self, newline_delimited: bool) -> Self {
+        self.newline_delimited = newline_delimited;
+        self
+    }
+
+    /// Set the time a connection may stay idle before being closed, 5 minutes by default. `None`
+    /// keeps idle connections open.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::io::Read;
+    /// use std::net::TcpStream;
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use serde_gelf::TcpReceiver;
+    ///
+    /// let receiver = TcpReceiver::bind("127.0.0.1:0").unwrap()
+    ///     .set_read_timeout(Some(Duration::from_millis(50)));
+    /// let mut client = TcpStream::connect(receiver.local_addr().unwrap()).unwrap();
+    /// thread::spawn(move || receiver.serve(|_| {}));
+    ///
+    /// assert_eq!(client.read(&mut [0; 1]).unwrap(), 0);
+    /// ```
+    pub fn set_read_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    /// Set the maximum number of connections served at once, further connections being closed
+    /// and reported to the error handler.
+    pub fn set_max_connections(mut self, max_connections: usize) -> Self {
+        self.max_connections = max_connections;
+        self
+    }
+
+    /// Return a handle stopping [`serve`](#method.serve) from another thread.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use]
+    /// extern crate serde_gelf;
+    ///
+    /// use std::io::Read;
+    /// use std::net::TcpStream;
+    /// use std::sync::mpsc;
+    /// use std::thread;
+    /// use serde_gelf::{GelfRecordGetter, TcpReceiver, TcpSender};
+    ///
+    /// fn main() {
+    ///     let (errors, errors_rx) = mpsc::channel();
+    ///     let receiver = TcpReceiver::bind("127.0.0.1:0").unwrap()
+    ///         .set_max_connections(1)
+    ///         .set_error_handler(move |_, err| errors.send(err.to_string()).unwrap());
+    ///     let addr = receiver.local_addr().unwrap();
+    ///     let handle = receiver.stop_handle().unwrap();
+    ///
+    ///     let (records, rx) = mpsc::channel();
+    ///     let server = thread::spawn(move || receiver.serve(move |rec| records.send(rec).unwrap()));
+    ///
+    ///     let sender = TcpSender::new(addr).unwrap();
+    ///     sender.send(&gelf_record!("hello")).unwrap();
+    ///     assert_eq!(rx.recv().unwrap().message(), "hello");
+    ///
+    ///     // A second connection exceeds the limit and is closed.
+    ///     let mut client = TcpStream::connect(addr).unwrap();
+    ///     assert_eq!(client.read(&mut [0; 1]).unwrap(), 0);
+    ///     assert_eq!(errors_rx.recv().unwrap(), "too many connections, at most 1 are served");
+    ///
+    ///     handle.stop();
+    ///     server.join().unwrap();
+    /// }
+    /// ```
+    pub fn stop_handle(&self) -> io::Result<TcpStopHandle> {
+        let mut addr = self.listener.local_addr()?;
+        if addr.ip().is_unspecified() {
+            addr.set_ip(match addr.ip() {
+                IpAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+                IpAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+            });
+        }
+        Ok(TcpStopHandle { addr, stopped: self.stopped.clone() })
+    }
+
+    /// Set the handler called with the peer address and the error on malformed or oversized
+    /// frames and on connection errors. The peer address is `None` when accepting a connection
+    /// failed. Errors are ignored by default.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use serde_gelf::TcpReceiver;
+    ///
+    /// let receiver = TcpReceiver::bind("127.0.0.1:0").unwrap()
+    ///     .set_error_handler(|peer, err| eprintln!("{:?}: {}", peer, err));
+    /// ```
+    pub fn set_error_handler<F>(mut self, on_error: F) -> Self
+        where F: Fn(Option<SocketAddr>, io::Error) + Send + Sync + 'static {
+        self.on_error = Arc::new(on_error);
+        self
+    }
+
+    /// Accept connections until stopped, each one being read by its own thread which calls
+    /// `handler` for every received record. See [`stop_handle`](#method.stop_handle).
+    pub fn serve<F>(self, handler: F) where F: Fn(GelfRecord) + Send + Sync + 'static {
+        let handler = Arc::new(handler);
+        let connections = Arc::new(Mutex::new(Connections::default()));
+        for stream in self.listener.incoming() {
+            if self.stopped.load(Ordering::SeqCst) {
+                break;
+            }
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    (self.on_error)(None, err);
+                    continue;
+                }
+            };
+            let peer = stream.peer_addr().ok();
+            let id = match self.register(&connections, &stream) {
+                Ok(id) => id,
+                Err(err) => {
+                    (self.on_error)(peer, err);
+                    continue;
+                }
+            };
+            let frames = Frames {
+                max_frame_size: self.max_frame_size,
+                newline_delimited: self.newline_delimited,
+                on_error: self.on_error.clone(),
+            };
+            let handler = handler.clone();
+            let thread_connections = connections.clone();
+            let spawned = thread::Builder::new()
+                .name("serde_gelf".into())
+                .spawn(move || {
+                    frames.read(stream, |rec| handler(rec));
+                    lock(&thread_connections).streams.remove(&id);
+                });
+            if let Err(err) = spawned {
+                lock(&connections).streams.remove(&id);
+                (self.on_error)(peer, err);
+            }
+        }
+        for stream in lock(&connections).streams.values() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+
+    /// Apply the read timeout and keep a handle on the stream, unless there are too many
+    /// connections.
+    fn register(&self, connections: &Mutex<Connections>, stream: &TcpStream) -> io::Result<u64> {
+        let mut connections = lock(connections);
+        if connections.streams.len() >= self.max_connections {
+            return Err(io::Error::other(format!(
+                "too many connections, at most {} are served", self.max_connections
+            )));
+        }
+        stream.set_read_timeout(self.read_timeout)?;
+        let id = connections.next_id;
+        connections.next_id += 1;
+        connections.streams.insert(id, stream.try_clone()?);
+        Ok(id)
+    }
+}
+
+fn lock(connections: &Mutex<Connections>) -> MutexGuard<'_, Connections> {
+    connections.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Framing settings of a connection.
+struct Frames {
+    max_frame_size: usize,
+    newline_delimited: bool,
+    on_error: ErrorHandler,
+}
+
+impl Frames {
+    fn is_delimiter(&self, byte: u8) -> bool {
+        byte == b'\0' || (self.newline_delimited && byte == b'\n')
+    }
+
+    /// Read frames until the connection is closed.
+    fn read<F: Fn(GelfRecord)>(&self, stream: TcpStream, handler: F) {
+        let peer = stream.peer_addr().ok();
+        let mut reader = BufReader::new(stream);
+        let mut frame = Vec::new();
+        let mut oversized = false;
+        loop {
+            let (consumed, complete) = match reader.fill_buf() {
+                Ok([]) => {
+                    if !frame.is_empty() && !oversized {
+                        self.parse(peer, &frame, &handler);
+                    }
+                    return;
+                }
+                Ok(buffer) => match buffer.iter().position(|byte| self.is_delimiter(*byte)) {
+                    Some(position) => {
+                        frame.extend_from_slice(&buffer[..position]);
+                        (position + 1, true)
+                    }
+                    None => {
+                        frame.extend_from_slice(buffer);
+                        (buffer.len(), false)
+                    }
+                },
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    (self.on_error)(peer, err);
+                    return;
+                }
+            };
+            reader.consume(consumed);
+
+            if frame.len() > self.max_frame_size {
+                if !oversized {
+                    (self.on_error)(peer, io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("frame exceeds {} bytes", self.max_frame_size),
+                    ));
+                }
+                oversized = true;
+                frame.clear();
+            }
+            if complete {
+                if !oversized {
+                    self.parse(peer, &frame, &handler);
+                }
+                oversized = false;
+                frame.clear();
+            }
+        }
+    }
+
+    fn parse<F: Fn(GelfRecord)>(&self, peer: Option<SocketAddr>, frame: &[u8], handler: &F) {
+        let frame = match frame {
+            [frame @ .., b'\r'] if self.newline_delimited => frame,
+            frame => frame,
+        };
+        if frame.iter().all(u8::is_ascii_whitespace) {
+            return;
+        }
+        match serde_json::from_slice(frame) {
+            Ok(rec) => handler(rec),
+            Err(err) => (self.on_error)(peer, err.into()),
+        }
+    }
+}
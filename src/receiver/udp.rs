@@ -0,0 +1,141 @@
+// Copyright 2019-present, OVH SAS
+// All rights reserved.
+//
+// This OVH Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+use crate::chunk::{is_chunk, Reassembler};
+use crate::receiver::{decode, DEFAULT_MAX_MESSAGE_SIZE};
+use crate::record::GelfRecord;
+
+/// Default time allowed to receive every chunk of a message, as recommended by the GELF
+/// specification.
+pub const DEFAULT_CHUNK_TIMEOUT: Duration = Duration::from_secs(5);
+/// Default maximum size of the chunks held while messages are reassembled.
+pub const DEFAULT_MAX_PENDING_BYTES: usize = 32 * 1024 * 1024;
+
+/// Receive [`GelfRecord`](struct.GelfRecord.html) over UDP.
+///
+/// Chunked messages are reassembled, those which are not complete within the chunk timeout or
+/// exceed the memory cap are dropped. GZIP and ZLIB payloads are decompressed when the
+/// `compression` feature is enabled.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate serde_gelf;
+///
+/// use serde_gelf::{GelfRecordGetter, UdpReceiver, UdpSender};
+///
+/// fn main() {
+///     let mut receiver = UdpReceiver::bind("127.0.0.1:0").unwrap();
+///     let sender = UdpSender::new(receiver.local_addr().unwrap()).unwrap().set_mtu(100);
+///
+///     let message = "x".repeat(500);
+///     sender.send(&gelf_record!("{}", message)).unwrap();
+///
+///     let rec = receiver.incoming().next().unwrap().unwrap();
+///     assert_eq!(rec.message(), message);
+/// }
+/// ```
+pub struct UdpReceiver {
+    socket: UdpSocket,
+    reassembler: Reassembler,
+    max_message_size: usize,
+    buffer: Vec<u8>,
+}
+
+impl UdpReceiver {
+    /// Create a receiver listening on the given address.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<UdpReceiver> {
+        Ok(UdpReceiver {
+            socket: UdpSocket::bind(addr)?,
+            reassembler: Reassembler::new(DEFAULT_CHUNK_TIMEOUT, DEFAULT_MAX_PENDING_BYTES),
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            buffer: vec![0; 65536],
+        })
+    }
+
+    /// Return the address the receiver listens on.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Set the time allowed to receive every chunk of a message.
+    pub fn set_chunk_timeout(mut self, timeout: Duration) -> Self {
+        self.reassembler.set_timeout(timeout);
+        self
+    }
+
+    /// Set the maximum size of the chunks held while messages are reassembled, the oldest
+    /// messages being dropped beyond it.
+    pub fn set_max_pending_bytes(mut self, max_bytes: usize) -> Self {
+        self.reassembler.set_max_bytes(max_bytes);
+        self
+    }
+
+    /// Set the maximum size of a payload, after decompression.
+    pub fn set_max_message_size(mut self, size: usize) -> Self {
+        self.max_message_size = size;
+        self
+    }
+
+    /// Set the read timeout of the socket, `None` blocking until a datagram is received.
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.socket.set_read_timeout(timeout)
+    }
+
+    /// Return the number of chunked messages dropped because of the chunk timeout or the memory
+    /// cap.
+    pub fn dropped(&self) -> usize {
+        self.reassembler.dropped()
+    }
+
+    /// Return the number of chunked messages being reassembled.
+    pub fn pending(&self) -> usize {
+        self.reassembler.pending()
+    }
+
+    /// Wait for the next record.
+    ///
+    /// Fails on socket errors and on invalid datagrams, the receiver remaining usable.
+    pub fn recv(&mut self) -> io::Result<GelfRecord> {
+        loop {
+            let size = self.socket.recv(&mut self.buffer)?;
+            let datagram = &self.buffer[..size];
+            if !is_chunk(datagram) {
+                return decode(datagram, self.max_message_size);
+            }
+            if let Some(payload) = self.reassembler.push(datagram, Instant::now())? {
+                return decode(&payload, self.max_message_size);
+            }
+        }
+    }
+
+    /// Return an endless iterator over the received records, see [`recv`](#method.recv).
+    pub fn incoming(&mut self) -> UdpIncoming<'_> {
+        UdpIncoming { receiver: self }
+    }
+}
+
+/// Iterator over the records received by an [`UdpReceiver`](struct.UdpReceiver.html).
+pub struct UdpIncoming<'a> {
+    receiver: &'a mut UdpReceiver,
+}
+
+impl<'a> Iterator for UdpIncoming<'a> {
+    type Item = io::Result<GelfRecord>;
+
+    fn next(&mut self) -> Option<io::Result<GelfRecord>> {
+        Some(self.receiver.recv())
+    }
+}
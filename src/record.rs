@@ -8,20 +8,30 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
-use std::collections::BTreeMap;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::time::SystemTime;
 
-use serde_value::Value;
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Unexpected, Visitor};
+use serde::ser::{self, Serialize, SerializeMap, Serializer};
+use serde_value::{SerializerError, Value};
 
+use crate::error::Error;
+use crate::flatten::{type_suffix, FlattenOptions};
+use crate::host::default_host;
+use crate::timestamp::{self, PreciseTimestamp, TimestampPrecision};
 use crate::level::GelfLevel;
-use crate::to_flat_dict;
+use crate::ser::serialize_flat_entries;
+use crate::to_flat_dict_with;
+use crate::validation::invalid_fields;
 
 /// Builder for [`GelfRecord`](struct.GelfRecord.html).
 ///
 /// # Examples
 ///
 /// ```rust
-/// use serde_gelf::{GelfRecord, GelfLevel};
+/// use serde_gelf::{GelfRecord, GelfRecordBuilder, GelfLevel};
 ///
 /// let rec = GelfRecord::new()
 ///     .set_file("main.rs".into())
@@ -43,7 +53,7 @@ pub trait GelfRecordBuilder {
     ///
     /// ```rust
     ///
-    /// use serde_gelf::GelfRecord;
+    /// use serde_gelf::{GelfRecord, GelfRecordBuilder};
     /// use std::time::{SystemTime, UNIX_EPOCH};
     ///
     /// let rec = GelfRecord::new()
@@ -54,36 +64,214 @@ pub trait GelfRecordBuilder {
     ///
     /// ```
     fn set_timestamp(self, timestamp: f64) -> Self;
+    /// Set `GelfRecord.timestamp` from a `SystemTime`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use serde_gelf::{GelfRecord, GelfRecordBuilder};
+    /// use std::time::SystemTime;
+    ///
+    /// let rec = GelfRecord::new()
+    ///     .set_system_time(SystemTime::now());
+    /// ```
+    fn set_system_time(self, time: SystemTime) -> Self where Self: Sized {
+        self.set_timestamp(timestamp::from_system_time(time))
+    }
+    /// Set `GelfRecord.timestamp` from a `chrono::DateTime<Utc>`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use serde_gelf::{GelfRecord, GelfRecordBuilder};
+    ///
+    /// let datetime = chrono::DateTime::from_timestamp(1554907321, 500_000_000).unwrap();
+    /// let rec = GelfRecord::new().set_chrono_datetime(datetime);
+    /// assert!(serde_json::to_string(&rec).unwrap().contains(r#""timestamp":1554907321.5,"#));
+    /// ```
+    #[cfg(feature = "chrono")]
+    fn set_chrono_datetime(self, datetime: chrono::DateTime<chrono::Utc>) -> Self where Self: Sized {
+        self.set_timestamp(timestamp::from_chrono(datetime))
+    }
+    /// Set `GelfRecord.timestamp` from a `time::OffsetDateTime`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use serde_gelf::{GelfRecord, GelfRecordBuilder};
+    ///
+    /// let datetime = time::OffsetDateTime::from_unix_timestamp_nanos(1_554_907_321_500_000_000).unwrap();
+    /// let rec = GelfRecord::new().set_offset_datetime(datetime);
+    /// assert!(serde_json::to_string(&rec).unwrap().contains(r#""timestamp":1554907321.5,"#));
+    /// ```
+    #[cfg(feature = "time")]
+    fn set_offset_datetime(self, datetime: time::OffsetDateTime) -> Self where Self: Sized {
+        self.set_timestamp(timestamp::from_offset_datetime(datetime))
+    }
     /// Extend a non-flatten dict to `GelfRecord.additional_fields`.
     ///
     /// # Example
     ///
     /// ```rust
     /// use std::collections::BTreeMap;
-    /// use serde_gelf::{GelfRecord, GelfLevel};
+    /// use serde_gelf::{GelfRecord, GelfRecordBuilder, GelfLevel};
     ///
     /// let mut extra = BTreeMap::new();
-    /// extra.insert("integer".into(),  serde_value::Value::I8(10));
+    /// extra.insert(serde_value::Value::String("integer".into()), serde_value::Value::I8(10));
     ///
     /// let rec = GelfRecord::new()
-    ///     .extend_additional_fields(extra);
+    ///     .add_additional_fields(extra);
     /// ```
+    ///
+    /// Fields which cannot be flattened are ignored, see
+    /// [`try_add_additional_fields`](#method.try_add_additional_fields) to catch the error.
     fn add_additional_fields(self, additional_fields: BTreeMap<Value, Value>) -> Self;
+    /// Extend a non-flatten dict to `GelfRecord.additional_fields`, failing if it cannot be
+    /// flattened.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::collections::BTreeMap;
+    /// use serde_gelf::{GelfRecord, GelfRecordBuilder};
+    ///
+    /// let mut extra = BTreeMap::new();
+    /// extra.insert(serde_value::Value::Seq(vec![]), serde_value::Value::I8(10));
+    ///
+    /// assert!(GelfRecord::new().try_add_additional_fields(extra).is_err());
+    /// ```
+    fn try_add_additional_fields(self, additional_fields: BTreeMap<Value, Value>) -> Result<Self, Error> where Self: Sized {
+        Ok(self.extend_additional_fields(flatten_fields(&additional_fields, &FlattenOptions::default())?))
+    }
     /// Extend a already flatten dict to `GelfRecord.additional_fields`.
     ///
+    /// LDP suffixes are appended on serialization, see
+    /// [`GelfRecord::set_ldp_suffixes`](struct.GelfRecord.html#method.set_ldp_suffixes), keys
+    /// already ending in their suffix being kept as is.
+    ///
     /// # Example
     ///
     /// ```rust
     /// use std::collections::BTreeMap;
-    /// use serde_gelf::{GelfRecord, GelfLevel, to_flat_dict};
+    /// use serde_gelf::{to_flat_dict_with, FlattenOptions, GelfRecord, GelfRecordBuilder};
     ///
     /// let mut extra = BTreeMap::new();
-    /// extra.insert("integer".into(),  serde_value::Value::I8(10));
+    /// extra.insert(serde_value::Value::String("integer".into()), serde_value::Value::I8(10));
     ///
-    /// let rec = GelfRecord::new()
-    ///     .extend_additional_fields(to_flat_dict(&extra).unwrap());
+    /// let flat = to_flat_dict_with(&extra, &FlattenOptions::new().set_ldp_suffixes(false)).unwrap();
+    /// let rec = GelfRecord::new().extend_additional_fields(flat);
     /// ```
     fn extend_additional_fields(self, additional_fields: BTreeMap<Value, Value>) -> Self;
+    /// Add a single field to `GelfRecord.additional_fields`, flattening `value`. The field is
+    /// ignored if it cannot be flattened.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use serde_gelf::{GelfRecord, GelfRecordBuilder, GelfRecordGetter};
+    ///
+    /// let rec = GelfRecord::new()
+    ///     .add_additional_field("user_id", &5);
+    /// assert_eq!(rec.additional_fields().len(), 1);
+    /// ```
+    fn add_additional_field<T>(self, key: &str, value: &T) -> Self where Self: Sized, T: Serialize + ?Sized {
+        self.add_nested_additional_field(&[key], value)
+    }
+    /// Add a single field nested under `path` to `GelfRecord.additional_fields`, flattening
+    /// `value` as if it was found at this path of a dict. The field is ignored if it cannot be
+    /// flattened.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use serde_gelf::{GelfRecord, GelfRecordBuilder, GelfRecordGetter};
+    ///
+    /// let rec = GelfRecord::new()
+    ///     .add_nested_additional_field(&["user", "name"], "John");
+    /// assert!(rec.additional_fields().contains_key(&serde_value::Value::String("_user_name".into())));
+    /// ```
+    fn add_nested_additional_field<T>(self, path: &[&str], value: &T) -> Self where Self: Sized, T: Serialize + ?Sized {
+        match nested_fields(path, value) {
+            Ok(flat) => self.extend_additional_fields(flat),
+            Err(_) => self,
+        }
+    }
+    /// Add a single field nested under `path` to `GelfRecord.additional_fields`, failing if it
+    /// cannot be flattened.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::collections::BTreeMap;
+    /// use serde_gelf::{GelfRecord, GelfRecordBuilder};
+    ///
+    /// let mut ids = BTreeMap::new();
+    /// ids.insert(vec![1], "John");
+    ///
+    /// assert!(GelfRecord::new().try_add_nested_additional_field(&["user"], "John").is_ok());
+    /// assert!(GelfRecord::new().try_add_nested_additional_field(&["user"], &ids).is_err());
+    /// ```
+    fn try_add_nested_additional_field<T>(self, path: &[&str], value: &T) -> Result<Self, Error> where Self: Sized, T: Serialize + ?Sized {
+        Ok(self.extend_additional_fields(nested_fields(path, value)?))
+    }
+    /// Extend a non-flatten dict to `GelfRecord.additional_fields`, flattened using the given
+    /// [`FlattenOptions`](struct.FlattenOptions.html). LDP suffixes are not stored but appended on
+    /// serialization, see
+    /// [`GelfRecord::set_ldp_suffixes`](struct.GelfRecord.html#method.set_ldp_suffixes).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::collections::BTreeMap;
+    /// use serde_gelf::{FlattenOptions, GelfRecord, GelfRecordBuilder};
+    ///
+    /// let mut extra = BTreeMap::new();
+    /// extra.insert(serde_value::Value::String("integers".into()), serde_value::Value::Seq(vec![
+    ///     serde_value::Value::I8(10),
+    ///     serde_value::Value::I8(20),
+    /// ]));
+    ///
+    /// let rec = GelfRecord::new()
+    ///     .add_additional_fields_with(extra, &FlattenOptions::new().set_separator("."));
+    /// ```
+    fn add_additional_fields_with(self, additional_fields: BTreeMap<Value, Value>, options: &FlattenOptions) -> Self where Self: Sized {
+        match flatten_fields(&additional_fields, options) {
+            Ok(flat) => self.extend_additional_fields(flat),
+            Err(_) => self,
+        }
+    }
+    /// Extend a non-flatten dict to `GelfRecord.additional_fields`, flattened using the given
+    /// [`FlattenOptions`](struct.FlattenOptions.html), failing if it cannot be flattened.
+    fn try_add_additional_fields_with(self, additional_fields: BTreeMap<Value, Value>, options: &FlattenOptions) -> Result<Self, Error> where Self: Sized {
+        Ok(self.extend_additional_fields(flatten_fields(&additional_fields, options)?))
+    }
+    /// Extend a non-flatten dict to `GelfRecord.additional_fields`, rewriting the keys which are
+    /// not valid additional field names.
+    ///
+    /// The fields are left out if two keys are rewritten into the same name, use
+    /// [`try_add_additional_fields_with`](#method.try_add_additional_fields_with) with
+    /// [`FlattenOptions::set_sanitize`](struct.FlattenOptions.html#method.set_sanitize) to get the
+    /// error instead.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::collections::BTreeMap;
+    /// use serde_gelf::{GelfRecord, GelfRecordBuilder};
+    ///
+    /// let mut extra = BTreeMap::new();
+    /// extra.insert(serde_value::Value::String("user name".into()), serde_value::Value::I8(10));
+    ///
+    /// let rec = GelfRecord::new()
+    ///     .add_sanitized_additional_fields(extra);
+    /// assert!(rec.validate().is_ok());
+    /// ```
+    fn add_sanitized_additional_fields(self, additional_fields: BTreeMap<Value, Value>) -> Self where Self: Sized {
+        match flatten_fields(&additional_fields, &FlattenOptions::default().set_sanitize(true)) {
+            Ok(flat) => self.extend_additional_fields(flat),
+            Err(_) => self,
+        }
+    }
     /// Set `GelfRecord.facility`.
     fn set_facility(self, facility: String) -> Self;
     /// Set `GelfRecord.line`.
@@ -103,15 +291,19 @@ pub trait GelfRecordSetter {
     /// Return the `GelfRecord.level` as mutable.
     fn level_mut(&mut self) -> &mut GelfLevel;
     /// Return the `GelfRecord.additional_fields` as mutable.
-    fn additional_fields_mut(&mut self) -> &mut BTreeMap<String, Value>;
+    ///
+    /// Keys are expected to be already flatten, see [`to_flat_dict`](fn.to_flat_dict.html).
+    fn additional_fields_mut(&mut self) -> &mut BTreeMap<Value, Value>;
     /// Return the `GelfRecord.facility` as mutable.
     fn facility_mut(&mut self) -> &mut String;
     /// Return the `GelfRecord.line` as mutable.
     fn line_mut(&mut self) -> &mut u32;
-    /// Return the `GelfRecord.full_message` as mutable.
+    /// Return the `GelfRecord.file` as mutable.
     fn file_mut(&mut self) -> &mut String;
     /// Return the `GelfRecord.full_message` as mutable.
     fn full_message_mut(&mut self) -> &mut Option<String>;
+    /// Return the `GelfRecord.host` as mutable.
+    fn host_mut(&mut self) -> &mut String;
 }
 
 /// Trait to access to [`GelfRecord`](struct.GelfRecord.html) attributes.
@@ -120,6 +312,8 @@ pub trait GelfRecordGetter {
     fn message(&self) -> String;
     /// Return the `GelfRecord.timestamp` attribute.
     fn timestamp(&self) -> f64;
+    /// Return the `GelfRecord.timestamp` attribute as a `SystemTime`.
+    fn system_time(&self) -> SystemTime { timestamp::to_system_time(self.timestamp()) }
     /// Return the `GelfRecord.level` attribute.
     fn level(&self) -> GelfLevel;
     /// Return the `GelfRecord.additional_fields` attribute.
@@ -134,10 +328,12 @@ pub trait GelfRecordGetter {
     fn version() -> &'static str { "1.1" }
     /// Return the `GelfRecord.full_message` attribute.
     fn full_message(&self) -> Option<String> { None }
+    /// Return the `GelfRecord.host` attribute.
+    fn host(&self) -> String { default_host() }
 }
 
 /// Structure which represent a log record.
-#[derive(Serialize, Debug, Clone)]
+#[derive(Debug, Clone)]
 pub struct GelfRecord {
     /// Source of the message that can i.e. the module path which created the log entry.
     facility: String,
@@ -145,33 +341,430 @@ pub struct GelfRecord {
     file: String,
     /// The name of the host, source or application that sent this message.
     host: String,
-    /// The level equal to the standard syslog levels.
-    level: u32,
-    #[serde(rename = "_levelname")]
-    levelname: String,
+    /// The level equal to the standard syslog levels, serialized along with its name as
+    /// `_levelname`.
+    level: GelfLevel,
     /// The line in a file that caused the log entry.
     line: u32,
     /// A short descriptive message.
     short_message: String,
     /// Seconds since UNIX epoch with optional decimal places for milliseconds.
-    #[serde(default = "now")]
     timestamp: f64,
+    /// Precision of the serialized timestamp.
+    timestamp_precision: Option<TimestampPrecision>,
+    /// Whether LDP type suffixes are appended to the additional field names on serialization.
+    ldp_suffixes: bool,
     /// GELF spec version.
     version: String,
     /// A long message that can i.e. contain a backtrace.
-    #[serde(skip_serializing_if = "Option::is_none")]
     full_message: Option<String>,
     /// Every field you send and prefix with an underscore (_) will be treated as an additional
     /// field. Allowed characters in field names are any word character (letter, number,
     /// underscore), dashes and dots. The verifying regular expression is: ^[\w\.\-]*$.
-    #[serde(flatten)]
     additional_fields: BTreeMap<Value, Value>,
 }
 
+impl GelfRecord {
+    /// Set `GelfRecord.host`, which defaults to [`default_host`](fn.default_host.html).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use serde_gelf::{GelfRecord, GelfRecordBuilder, GelfRecordGetter};
+    ///
+    /// let rec = GelfRecord::new().set_host("example.org".into());
+    /// assert_eq!(rec.host(), "example.org");
+    /// ```
+    pub fn set_host(mut self, host: String) -> Self {
+        self.host = host;
+        self
+    }
+
+    /// Set the precision of the serialized `GelfRecord.timestamp`, `None` keeping every decimal.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use serde_gelf::{GelfRecord, GelfRecordBuilder, TimestampPrecision};
+    ///
+    /// let rec = GelfRecord::new()
+    ///     .set_timestamp(1554907321.6123526)
+    ///     .set_timestamp_precision(Some(TimestampPrecision::Millis));
+    /// assert!(serde_json::to_string(&rec).unwrap().contains(r#""timestamp":1554907321.612,"#));
+    /// ```
+    pub fn set_timestamp_precision(mut self, precision: Option<TimestampPrecision>) -> Self {
+        self.timestamp_precision = precision;
+        self
+    }
+
+    /// Append the LDP type suffixes to the additional field names on serialization, see
+    /// [`FlattenOptions::set_ldp_suffixes`](struct.FlattenOptions.html#method.set_ldp_suffixes).
+    ///
+    /// Enabled by default with the *ovh-ldp* feature. Additional fields are stored without
+    /// suffix and get their suffix when enabled, unless their name already ends with it, so that
+    /// the same record can be sent to LDP and to a plain Graylog. Serialization fails if two names
+    /// end up the same.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use serde_gelf::{GelfRecord, GelfRecordBuilder};
+    ///
+    /// let rec = GelfRecord::new().add_additional_field("retries", &3);
+    /// let ldp = serde_json::to_string(&rec.clone().set_ldp_suffixes(true)).unwrap();
+    /// let plain = serde_json::to_string(&rec.set_ldp_suffixes(false)).unwrap();
+    /// assert!(ldp.contains(r#""_retries_long":3"#));
+    /// assert!(plain.contains(r#""_retries":3"#));
+    ///
+    /// // Both fields are serialized as `_count_long`.
+    /// let rec = GelfRecord::new()
+    ///     .add_additional_field("count_long", "many")
+    ///     .add_additional_field("count", &3);
+    /// assert!(serde_json::to_string(&rec.clone().set_ldp_suffixes(false)).is_ok());
+    /// assert!(serde_json::to_string(&rec.set_ldp_suffixes(true)).is_err());
+    /// ```
+    pub fn set_ldp_suffixes(mut self, ldp_suffixes: bool) -> Self {
+        self.ldp_suffixes = ldp_suffixes;
+        self
+    }
+
+    /// Check the additional field names against the
+    /// [`GELF Payload Specification`](http://docs.graylog.org/en/3.0/pages/gelf.html#gelf-payload-specification),
+    /// returning every invalid name as [`Error::InvalidFields`](enum.Error.html#variant.InvalidFields).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::collections::BTreeMap;
+    /// use serde_gelf::{Error, GelfRecord, GelfRecordBuilder};
+    ///
+    /// let mut extra = BTreeMap::new();
+    /// extra.insert(serde_value::Value::String("_user name".into()), serde_value::Value::I8(10));
+    /// extra.insert(serde_value::Value::String("_id".into()), serde_value::Value::I8(10));
+    ///
+    /// let rec = GelfRecord::new().extend_additional_fields(extra);
+    /// match rec.validate() {
+    ///     Err(Error::InvalidFields(fields)) => assert_eq!(fields, vec!["_id".to_string(), "_user name".to_string()]),
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn validate(&self) -> Result<(), Error> {
+        let invalid = invalid_fields(&self.additional_fields);
+        if invalid.is_empty() { Ok(()) } else { Err(Error::InvalidFields(invalid)) }
+    }
+
+    /// Finish building the record, failing if some additional field names are invalid.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::collections::BTreeMap;
+    /// use serde_gelf::{Error, GelfRecord, GelfRecordBuilder};
+    ///
+    /// let mut extra = BTreeMap::new();
+    /// extra.insert(serde_value::Value::String("user name".into()), serde_value::Value::String("John".into()));
+    ///
+    /// let rec = GelfRecord::new()
+    ///     .set_message("hello".into())
+    ///     .try_add_additional_fields(extra)
+    ///     .and_then(GelfRecord::build);
+    /// match rec {
+    ///     Err(Error::InvalidFields(fields)) => assert_eq!(fields, vec!["_user name".to_string()]),
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn build(self) -> Result<GelfRecord, Error> {
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Serialize the record as a GELF JSON payload.
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Write the record as a GELF JSON payload followed by the flattened `fields`, without
+    /// building intermediate `serde_value::Value` trees for them.
+    ///
+    /// The fields are written after the record's own additional fields and are not validated.
+    /// LDP suffixes follow the record's [`set_ldp_suffixes`](#method.set_ldp_suffixes) setting
+    /// rather than the options one. Fails if a field has the same name as an entry of the record
+    /// or as another field.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::collections::BTreeMap;
+    /// use serde_gelf::{FlattenOptions, GelfRecord, GelfRecordBuilder, GelfRecordGetter};
+    ///
+    /// let mut extra = BTreeMap::new();
+    /// extra.insert("request_id", "42");
+    ///
+    /// let mut payload = Vec::new();
+    /// GelfRecord::new()
+    ///     .set_message("hello".into())
+    ///     .write_with_fields(&mut payload, &extra, &FlattenOptions::default())
+    ///     .unwrap();
+    /// let rec = serde_gelf::from_slice(&payload).unwrap();
+    /// assert_eq!(rec.additional_fields().get(&serde_value::Value::String("_request_id".into())), Some(&serde_value::Value::String("42".into())));
+    ///
+    /// // The record already has a `_request_id` field.
+    /// let rec = GelfRecord::new().add_additional_field("request_id", &"41");
+    /// assert!(rec.write_with_fields(Vec::new(), &extra, &FlattenOptions::default()).is_err());
+    /// ```
+    pub fn write_with_fields<W, S>(&self, writer: W, fields: &S, options: &FlattenOptions) -> Result<(), Error>
+        where W: std::io::Write, S: Serialize + ?Sized {
+        write_entries(self.entries(), writer, fields, options)
+    }
+
+    /// Same as [`write_with_fields`](#method.write_with_fields) with LDP suffixes, leaving out
+    /// the additional field named `skipped` so that `fields` can replace it.
+    #[cfg(feature = "ovh-ldp-sender")]
+    pub(crate) fn write_ldp<W, S>(&self, writer: W, fields: &S, options: &FlattenOptions, skipped: &str) -> Result<(), Error>
+        where W: std::io::Write, S: Serialize + ?Sized {
+        let entries = Entries { skipped: Some(skipped), ldp_suffixes: true, ..self.entries() };
+        write_entries(entries, writer, fields, options)
+    }
+
+    /// Borrow the GELF entries of the record.
+    fn entries(&self) -> Entries<'_> {
+        Entries {
+            facility: &self.facility,
+            file: &self.file,
+            host: &self.host,
+            level: self.level,
+            line: self.line,
+            short_message: &self.short_message,
+            timestamp: PreciseTimestamp(self.timestamp, self.timestamp_precision),
+            version: &self.version,
+            full_message: self.full_message.as_deref(),
+            additional_fields: &self.additional_fields,
+            skipped: None,
+            ldp_suffixes: self.ldp_suffixes,
+        }
+    }
+}
+
+/// Write the entries of a record followed by the flattened `fields`.
+fn write_entries<W, S>(entries: Entries<'_>, writer: W, fields: &S, options: &FlattenOptions) -> Result<(), Error>
+    where W: std::io::Write, S: Serialize + ?Sized {
+    let options = options.clone().set_ldp_suffixes(entries.ldp_suffixes);
+    let mut serializer = serde_json::Serializer::new(writer);
+    let mut map = serializer.serialize_map(None)?;
+    entries.serialize_into(&mut map)?;
+    serialize_flat_entries(&mut map, fields, &options, entries.keys())?;
+    map.end()?;
+    Ok(())
+}
+
+/// Borrowed GELF entries, shared by the owned and the borrowed records so that both serialize
+/// identically.
+pub(crate) struct Entries<'r> {
+    pub(crate) facility: &'r str,
+    pub(crate) file: &'r str,
+    pub(crate) host: &'r str,
+    pub(crate) level: GelfLevel,
+    pub(crate) line: u32,
+    pub(crate) short_message: &'r str,
+    pub(crate) timestamp: PreciseTimestamp,
+    pub(crate) version: &'r str,
+    pub(crate) full_message: Option<&'r str>,
+    pub(crate) additional_fields: &'r BTreeMap<Value, Value>,
+    /// Name of an additional field left out of the serialized entries.
+    pub(crate) skipped: Option<&'r str>,
+    pub(crate) ldp_suffixes: bool,
+}
+
+impl<'r> Entries<'r> {
+    /// Serialize the entries into `map`.
+    pub(crate) fn serialize_into<M>(&self, map: &mut M) -> Result<(), M::Error> where M: SerializeMap {
+        map.serialize_entry("facility", self.facility)?;
+        map.serialize_entry("file", self.file)?;
+        map.serialize_entry("host", self.host)?;
+        map.serialize_entry("level", &(self.level as u32))?;
+        map.serialize_entry("_levelname", &String::from(self.level))?;
+        map.serialize_entry("line", &self.line)?;
+        map.serialize_entry("short_message", self.short_message)?;
+        map.serialize_entry("timestamp", &self.timestamp)?;
+        map.serialize_entry("version", self.version)?;
+        if let Some(full_message) = self.full_message {
+            map.serialize_entry("full_message", full_message)?;
+        }
+        // Unsuffixed names are unique as map keys, suffixed ones may collide.
+        let mut names = HashSet::new();
+        for (key, value) in self.additional_fields() {
+            match key {
+                Value::String(name) if self.ldp_suffixes => {
+                    let name = self.field_name(name, value);
+                    if !names.insert(name.clone()) {
+                        return Err(ser::Error::custom(format_args!("duplicate field `{}`", name)));
+                    }
+                    map.serialize_entry(&*name, value)?
+                }
+                Value::String(name) => map.serialize_entry(name, value)?,
+                _ => map.serialize_entry(key, value)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Iterate over the additional fields, the skipped one excepted.
+    fn additional_fields(&self) -> impl Iterator<Item = (&'r Value, &'r Value)> + '_ {
+        self.additional_fields.iter().filter(move |(key, _)| match (key, self.skipped) {
+            (Value::String(name), Some(skipped)) => name != skipped,
+            _ => true,
+        })
+    }
+
+    /// Return the serialized name of an additional field.
+    ///
+    /// Names already ending in their suffix, i.e. flattened by [`to_flat_dict`](fn.to_flat_dict.html)
+    /// with the *ovh-ldp* feature, are kept as is.
+    fn field_name<'k>(&self, name: &'k str, value: &Value) -> Cow<'k, str> {
+        match type_suffix(value) {
+            suffix if self.ldp_suffixes && !name.ends_with(suffix) => Cow::Owned(format!("{}{}", name, suffix)),
+            _ => Cow::Borrowed(name),
+        }
+    }
+
+    /// Return the keys written by [`serialize_into`](#method.serialize_into).
+    pub(crate) fn keys(&self) -> HashSet<String> {
+        let mut keys: HashSet<String> = ["facility", "file", "host", "level", "_levelname", "line", "short_message", "timestamp", "version"]
+            .iter().map(|key| key.to_string()).collect();
+        if self.full_message.is_some() {
+            keys.insert("full_message".to_string());
+        }
+        for (key, value) in self.additional_fields() {
+            if let Value::String(name) = key {
+                keys.insert(self.field_name(name, value).into_owned());
+            }
+        }
+        keys
+    }
+}
+
+/// Flatten a value as if it was found at `path` of a dict.
+fn nested_fields<T>(path: &[&str], value: &T) -> Result<BTreeMap<Value, Value>, SerializerError> where T: Serialize + ?Sized {
+    let nested = path.iter().rev().fold(serde_value::to_value(value)?, |value, key| {
+        let mut map = BTreeMap::new();
+        map.insert(Value::String(key.to_string()), value);
+        Value::Map(map)
+    });
+    flatten_fields(&nested, &FlattenOptions::default())
+}
+
+/// Flatten additional fields without LDP suffixes, those being appended on serialization
+/// according to the record settings.
+pub fn flatten_fields<S>(value: &S, options: &FlattenOptions) -> Result<BTreeMap<Value, Value>, SerializerError> where S: Serialize + ?Sized {
+    to_flat_dict_with(value, &options.clone().set_ldp_suffixes(false))
+}
+
+impl Serialize for GelfRecord {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        let mut map = serializer.serialize_map(None)?;
+        self.entries().serialize_into(&mut map)?;
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for GelfRecord {
+    fn deserialize<D>(deserializer: D) -> Result<GelfRecord, D::Error> where D: Deserializer<'de> {
+        deserializer.deserialize_map(GelfRecordVisitor)
+    }
+}
+
+/// Visitor which maps a GELF payload back into a [`GelfRecord`](struct.GelfRecord.html).
+///
+/// `version`, `host` and `short_message` are mandatory, every key prefixed with an underscore is
+/// stored in `additional_fields` and any other unknown key is rejected.
+struct GelfRecordVisitor;
+
+impl<'de> Visitor<'de> for GelfRecordVisitor {
+    type Value = GelfRecord;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a GELF payload")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<GelfRecord, A::Error> where A: MapAccess<'de> {
+        let mut facility: Option<String> = None;
+        let mut file: Option<String> = None;
+        let mut host: Option<String> = None;
+        let mut level: Option<u32> = None;
+        let mut line: Option<u32> = None;
+        let mut short_message: Option<String> = None;
+        let mut timestamp: Option<f64> = None;
+        let mut version: Option<String> = None;
+        let mut full_message: Option<Option<String>> = None;
+        let mut additional_fields = BTreeMap::new();
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "facility" => set_once(&mut facility, "facility", map.next_value()?)?,
+                "file" => set_once(&mut file, "file", map.next_value()?)?,
+                "host" => set_once(&mut host, "host", map.next_value()?)?,
+                "level" => {
+                    let value: u64 = map.next_value()?;
+                    if value > 7 {
+                        return Err(de::Error::invalid_value(Unexpected::Unsigned(value), &"a syslog level between 0 and 7"));
+                    }
+                    set_once(&mut level, "level", value as u32)?
+                }
+                "line" => set_once(&mut line, "line", map.next_value()?)?,
+                "short_message" => set_once(&mut short_message, "short_message", map.next_value()?)?,
+                "timestamp" => set_once(&mut timestamp, "timestamp", map.next_value()?)?,
+                "version" => set_once(&mut version, "version", map.next_value()?)?,
+                "full_message" => set_once(&mut full_message, "full_message", map.next_value()?)?,
+                // The level name is derived from the level.
+                "_levelname" => { map.next_value::<de::IgnoredAny>()?; }
+                _ if key.starts_with('_') => {
+                    let value: Value = map.next_value()?;
+                    if additional_fields.contains_key(&Value::String(key.clone())) {
+                        return Err(de::Error::custom(format_args!("duplicate field `{}`", key)));
+                    }
+                    additional_fields.insert(Value::String(key), value);
+                }
+                _ => return Err(de::Error::unknown_field(&key, FIELDS)),
+            }
+        }
+
+        Ok(GelfRecord {
+            facility: facility.unwrap_or_default(),
+            file: file.unwrap_or_default(),
+            host: host.ok_or_else(|| de::Error::missing_field("host"))?,
+            level: level.map(GelfLevel::from).unwrap_or_default(),
+            line: line.unwrap_or(0),
+            short_message: short_message.ok_or_else(|| de::Error::missing_field("short_message"))?,
+            timestamp: timestamp.unwrap_or_else(now),
+            timestamp_precision: None,
+            // Received names are kept as is, suffixes included.
+            ldp_suffixes: false,
+            version: version.ok_or_else(|| de::Error::missing_field("version"))?,
+            full_message: full_message.unwrap_or(None),
+            additional_fields,
+        })
+    }
+}
+
+/// Known GELF payload keys, additional fields excepted.
+const FIELDS: &[&str] = &[
+    "facility", "file", "full_message", "host", "level", "line", "short_message", "timestamp", "version",
+];
+
+/// Store a field value, failing if the field was already seen.
+fn set_once<T, E: de::Error>(slot: &mut Option<T>, name: &'static str, value: T) -> Result<(), E> {
+    match slot {
+        Some(_) => Err(E::duplicate_field(name)),
+        None => {
+            *slot = Some(value);
+            Ok(())
+        }
+    }
+}
+
 /// Default timestamp in seconds since UNIX epoch with optional decimal places for milliseconds.
-fn now() -> f64 {
-    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
-    now.as_secs() as f64 + f64::from(now.subsec_nanos()) / 1e9
+pub(crate) fn now() -> f64 {
+    timestamp::from_system_time(SystemTime::now())
 }
 
 impl GelfRecordBuilder for GelfRecord {
@@ -180,12 +773,13 @@ impl GelfRecordBuilder for GelfRecord {
         GelfRecord {
             facility: "main".to_string(),
             file: "main.rs".to_string(),
-            host: hostname::get_hostname().unwrap_or("localhost".to_string()),
-            level: GelfLevel::Alert as u32,
-            levelname: String::from(GelfLevel::Alert),
+            host: default_host(),
+            level: GelfLevel::Alert,
             line: 0,
             short_message: "".to_string(),
             timestamp: now(),
+            timestamp_precision: None,
+            ldp_suffixes: cfg!(feature = "ovh-ldp"),
             version: "1.1".to_string(),
             additional_fields: BTreeMap::new(),
             full_message: None,
@@ -199,8 +793,7 @@ impl GelfRecordBuilder for GelfRecord {
     }
     /// Set `GelfRecord.level`.
     fn set_level(mut self, level: GelfLevel) -> Self {
-        self.level = level as u32;
-        self.levelname = String::from(level);
+        self.level = level;
         self
     }
     /// Set `GelfRecord.timestamp`.
@@ -209,7 +802,7 @@ impl GelfRecordBuilder for GelfRecord {
     ///
     /// ```rust
     ///
-    /// use serde_gelf::GelfRecord;
+    /// use serde_gelf::{GelfRecord, GelfRecordBuilder};
     /// use std::time::{SystemTime, UNIX_EPOCH};
     ///
     /// let rec = GelfRecord::new()
@@ -224,28 +817,30 @@ impl GelfRecordBuilder for GelfRecord {
         self
     }
 
-    /// Extend a already flatten dict to `GelfRecord.additional_fields`.
+    /// Extend a non-flatten dict to `GelfRecord.additional_fields`.
     ///
     /// # Example
     ///
     /// ```rust
     /// use std::collections::BTreeMap;
-    /// use serde_gelf::{GelfRecord, GelfLevel, to_flat_dict};
+    /// use serde_gelf::{GelfRecord, GelfRecordBuilder, GelfLevel, to_flat_dict};
     ///
     /// let mut extra = BTreeMap::new();
-    /// extra.insert("integer".into(),  serde_value::Value::I8(10));
+    /// extra.insert(serde_value::Value::String("integer".into()), serde_value::Value::I8(10));
     ///
+    /// // Suffixed or not by `to_flat_dict`, the key is suffixed once.
     /// let rec = GelfRecord::new()
-    ///     .extend_additional_fields(to_flat_dict(&extra).unwrap());
+    ///     .extend_additional_fields(to_flat_dict(&extra).unwrap())
+    ///     .set_ldp_suffixes(true);
+    /// let json = serde_json::to_string(&rec).unwrap();
+    /// assert!(json.contains(r#""_integer_long":10"#));
+    /// assert!(!json.contains("_long_long"));
     /// ```
     fn add_additional_fields(mut self, additional_fields: BTreeMap<Value, Value>) -> Self {
-        match serde_value::to_value(&additional_fields) {
-            Ok(value) => {
-                self.additional_fields.extend( to_flat_dict(&value).unwrap());
-                self
-            }
-            Err(_) => self
+        if let Ok(flat) = flatten_fields(&additional_fields, &FlattenOptions::default()) {
+            self.additional_fields.extend(flat);
         }
+        self
     }
 
     /// Extend a already flatten dict to `GelfRecord.additional_fields`.
@@ -254,13 +849,13 @@ impl GelfRecordBuilder for GelfRecord {
     ///
     /// ```rust
     /// use std::collections::BTreeMap;
-    /// use serde_gelf::{GelfRecord, GelfLevel, to_flat_dict};
+    /// use serde_gelf::{to_flat_dict_with, FlattenOptions, GelfRecord, GelfRecordBuilder};
     ///
     /// let mut extra = BTreeMap::new();
-    /// extra.insert("integer".into(),  serde_value::Value::I8(10));
+    /// extra.insert(serde_value::Value::String("integer".into()), serde_value::Value::I8(10));
     ///
-    /// let rec = GelfRecord::new()
-    ///     .extend_additional_fields(to_flat_dict(&extra).unwrap());
+    /// let flat = to_flat_dict_with(&extra, &FlattenOptions::new().set_ldp_suffixes(false)).unwrap();
+    /// let rec = GelfRecord::new().extend_additional_fields(flat);
     /// ```
     fn extend_additional_fields(mut self, additional_fields: BTreeMap<Value, Value>) -> Self {
         self.additional_fields.extend(additional_fields);
@@ -297,7 +892,7 @@ impl GelfRecordGetter for GelfRecord {
     /// Return the `GelfRecord.timestamp` attribute.
     fn timestamp(&self) -> f64 { self.timestamp }
     /// Return the `GelfRecord.level` attribute.
-    fn level(&self) -> GelfLevel { GelfLevel::from(self.level) }
+    fn level(&self) -> GelfLevel { self.level }
     /// Return the `GelfRecord.additional_fields` attribute.
     fn additional_fields(&self) -> BTreeMap<Value, Value> { self.additional_fields.clone() }
     /// Return the `GelfRecord.facility` attribute.
@@ -310,6 +905,54 @@ impl GelfRecordGetter for GelfRecord {
     fn version() -> &'static str { "1.1" }
     /// Return the `GelfRecord.full_message` attribute.
     fn full_message(&self) -> Option<String> { self.full_message.clone() }
+    /// Return the `GelfRecord.host` attribute.
+    fn host(&self) -> String { self.host.clone() }
+}
+
+impl GelfRecordSetter for GelfRecord {
+    /// Return the `GelfRecord.message` as mutable.
+    fn message_mut(&mut self) -> &mut String { &mut self.short_message }
+    /// Return the `GelfRecord.timestamp` as mutable.
+    fn timestamp_mut(&mut self) -> &mut f64 { &mut self.timestamp }
+    /// Return the `GelfRecord.level` as mutable.
+    fn level_mut(&mut self) -> &mut GelfLevel { &mut self.level }
+    /// Return the `GelfRecord.additional_fields` as mutable.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use serde_gelf::{GelfRecord, GelfRecordBuilder, GelfRecordSetter};
+    /// use serde_value::Value;
+    ///
+    /// let mut rec = GelfRecord::new();
+    /// rec.additional_fields_mut().insert(Value::String("_password".into()), Value::String("secret".into()));
+    ///
+    /// // Redact sensitive fields in place.
+    /// if let Some(value) = rec.additional_fields_mut().get_mut(&Value::String("_password".into())) {
+    ///     *value = Value::String("***".into());
+    /// }
+    /// ```
+    fn additional_fields_mut(&mut self) -> &mut BTreeMap<Value, Value> { &mut self.additional_fields }
+    /// Return the `GelfRecord.facility` as mutable.
+    fn facility_mut(&mut self) -> &mut String { &mut self.facility }
+    /// Return the `GelfRecord.line` as mutable.
+    fn line_mut(&mut self) -> &mut u32 { &mut self.line }
+    /// Return the `GelfRecord.file` as mutable.
+    fn file_mut(&mut self) -> &mut String { &mut self.file }
+    /// Return the `GelfRecord.full_message` as mutable.
+    fn full_message_mut(&mut self) -> &mut Option<String> { &mut self.full_message }
+    /// Return the `GelfRecord.host` as mutable.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use serde_gelf::{GelfRecord, GelfRecordBuilder, GelfRecordGetter, GelfRecordSetter};
+    ///
+    /// let mut rec = GelfRecord::new().set_host("web-1".into());
+    /// rec.host_mut().push_str(".example.org");
+    /// assert_eq!(rec.host(), "web-1.example.org");
+    /// ```
+    fn host_mut(&mut self) -> &mut String { &mut self.host }
 }
 
 impl<'a> From<&log::Record<'a>> for GelfRecord {
@@ -333,6 +976,7 @@ impl<R: GelfRecordGetter> From<&R> for GelfRecord {
             .set_timestamp(record.timestamp())
             .set_message(record.message())
             .set_full_message(record.full_message())
+            .set_host(record.host())
             .add_additional_fields(record.additional_fields())
     }
 }
@@ -0,0 +1,238 @@
+// Copyright 2019-present, OVH SAS
+// All rights reserved.
+//
+// This OVH Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_value::Value;
+
+use crate::host::default_host;
+use crate::level::GelfLevel;
+use crate::record::{now, Entries, GelfRecord, GelfRecordBuilder, GelfRecordGetter};
+use crate::timestamp::{PreciseTimestamp, TimestampPrecision};
+
+/// Log record borrowing its strings, i.e. `module_path!()`, `file!()` or a message which does
+/// not need formatting.
+///
+/// It serializes exactly like [`GelfRecord`](struct.GelfRecord.html) and converts into it when
+/// the record needs to outlive the call site.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_gelf::{GelfLevel, GelfRecord, GelfRecordBuilder, GelfRecordRef};
+///
+/// let rec = GelfRecordRef::new()
+///     .set_facility(module_path!())
+///     .set_file(file!())
+///     .set_line(line!())
+///     .set_level(GelfLevel::Notice)
+///     .set_message("Hello")
+///     .set_host("example.org");
+///
+/// let owned = GelfRecord::from(rec.clone());
+/// assert_eq!(serde_json::to_string(&rec).unwrap(), serde_json::to_string(&owned).unwrap());
+/// ```
+#[derive(Debug, Clone)]
+pub struct GelfRecordRef<'a> {
+    facility: Cow<'a, str>,
+    file: Cow<'a, str>,
+    /// `None` stands for [`default_host`](fn.default_host.html), resolved on serialization.
+    host: Option<Cow<'a, str>>,
+    level: GelfLevel,
+    line: u32,
+    short_message: Cow<'a, str>,
+    timestamp: f64,
+    timestamp_precision: Option<TimestampPrecision>,
+    ldp_suffixes: bool,
+    full_message: Option<Cow<'a, str>>,
+    /// Already flatten additional fields, see [`to_flat_dict`](fn.to_flat_dict.html).
+    additional_fields: BTreeMap<Value, Value>,
+}
+
+impl<'a> Default for GelfRecordRef<'a> {
+    fn default() -> GelfRecordRef<'a> {
+        GelfRecordRef {
+            facility: Cow::Borrowed("main"),
+            file: Cow::Borrowed("main.rs"),
+            host: None,
+            level: GelfLevel::Alert,
+            line: 0,
+            short_message: Cow::Borrowed(""),
+            timestamp: now(),
+            timestamp_precision: None,
+            ldp_suffixes: cfg!(feature = "ovh-ldp"),
+            full_message: None,
+            additional_fields: BTreeMap::new(),
+        }
+    }
+}
+
+impl<'a> GelfRecordRef<'a> {
+    /// Construct a new record, with the same defaults as [`GelfRecord`](struct.GelfRecord.html).
+    pub fn new() -> GelfRecordRef<'a> {
+        GelfRecordRef::default()
+    }
+
+    /// Set `GelfRecordRef.short_message`.
+    pub fn set_message<S: Into<Cow<'a, str>>>(mut self, short_message: S) -> Self {
+        self.short_message = short_message.into();
+        self
+    }
+
+    /// Set `GelfRecordRef.level`.
+    pub fn set_level(mut self, level: GelfLevel) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Set `GelfRecordRef.timestamp`.
+    pub fn set_timestamp(mut self, timestamp: f64) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Set the precision of the serialized `GelfRecordRef.timestamp`, `None` keeping every
+    /// decimal.
+    pub fn set_timestamp_precision(mut self, precision: Option<TimestampPrecision>) -> Self {
+        self.timestamp_precision = precision;
+        self
+    }
+
+    /// Append the LDP type suffixes to the additional field names on serialization, enabled by
+    /// default with the *ovh-ldp* feature.
+    pub fn set_ldp_suffixes(mut self, ldp_suffixes: bool) -> Self {
+        self.ldp_suffixes = ldp_suffixes;
+        self
+    }
+
+    /// Set `GelfRecordRef.facility`.
+    pub fn set_facility<S: Into<Cow<'a, str>>>(mut self, facility: S) -> Self {
+        self.facility = facility.into();
+        self
+    }
+
+    /// Set `GelfRecordRef.line`.
+    pub fn set_line(mut self, line: u32) -> Self {
+        self.line = line;
+        self
+    }
+
+    /// Set `GelfRecordRef.file`.
+    pub fn set_file<S: Into<Cow<'a, str>>>(mut self, file: S) -> Self {
+        self.file = file.into();
+        self
+    }
+
+    /// Set `GelfRecordRef.full_message`.
+    pub fn set_full_message<S: Into<Cow<'a, str>>>(mut self, full_message: Option<S>) -> Self {
+        self.full_message = full_message.map(Into::into);
+        self
+    }
+
+    /// Set `GelfRecordRef.host`, which defaults to [`default_host`](fn.default_host.html).
+    pub fn set_host<S: Into<Cow<'a, str>>>(mut self, host: S) -> Self {
+        self.host = Some(host.into());
+        self
+    }
+
+    /// Extend a already flatten dict to `GelfRecordRef.additional_fields`.
+    pub fn extend_additional_fields(mut self, additional_fields: BTreeMap<Value, Value>) -> Self {
+        self.additional_fields.extend(additional_fields);
+        self
+    }
+
+    /// Return the `GelfRecordRef.message` attribute.
+    pub fn message(&self) -> &str { &self.short_message }
+    /// Return the `GelfRecordRef.level` attribute.
+    pub fn level(&self) -> GelfLevel { self.level }
+    /// Return the `GelfRecordRef.facility` attribute.
+    pub fn facility(&self) -> &str { &self.facility }
+    /// Return the `GelfRecordRef.file` attribute.
+    pub fn file(&self) -> &str { &self.file }
+    /// Return the `GelfRecordRef.line` attribute.
+    pub fn line(&self) -> u32 { self.line }
+    /// Return the `GelfRecordRef.timestamp` attribute.
+    pub fn timestamp(&self) -> f64 { self.timestamp }
+    /// Return the `GelfRecordRef.full_message` attribute.
+    pub fn full_message(&self) -> Option<&str> { self.full_message.as_deref() }
+    /// Return the `GelfRecordRef.additional_fields` attribute.
+    pub fn additional_fields(&self) -> &BTreeMap<Value, Value> { &self.additional_fields }
+
+    /// Convert into an owned [`GelfRecord`](struct.GelfRecord.html).
+    pub fn into_owned(self) -> GelfRecord {
+        GelfRecord::from(self)
+    }
+}
+
+impl<'a> Serialize for GelfRecordRef<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        let default;
+        let host = match self.host {
+            Some(ref host) => host.as_ref(),
+            None => {
+                default = default_host();
+                default.as_str()
+            }
+        };
+        let mut map = serializer.serialize_map(None)?;
+        Entries {
+            facility: &self.facility,
+            file: &self.file,
+            host,
+            level: self.level,
+            line: self.line,
+            short_message: &self.short_message,
+            timestamp: PreciseTimestamp(self.timestamp, self.timestamp_precision),
+            version: GelfRecord::version(),
+            full_message: self.full_message(),
+            additional_fields: &self.additional_fields,
+            skipped: None,
+            ldp_suffixes: self.ldp_suffixes,
+        }.serialize_into(&mut map)?;
+        map.end()
+    }
+}
+
+impl<'a> From<&'a log::Record<'a>> for GelfRecordRef<'a> {
+    fn from(record: &'a log::Record<'a>) -> GelfRecordRef<'a> {
+        let message = match record.args().as_str() {
+            Some(message) => Cow::Borrowed(message),
+            None => Cow::Owned(record.args().to_string()),
+        };
+        GelfRecordRef::new()
+            .set_facility(record.target())
+            .set_file(record.module_path().unwrap_or(""))
+            .set_level(GelfLevel::from(record.level()))
+            .set_line(record.line().unwrap_or(0))
+            .set_message(message)
+    }
+}
+
+impl<'a> From<GelfRecordRef<'a>> for GelfRecord {
+    fn from(record: GelfRecordRef<'a>) -> GelfRecord {
+        let rec = GelfRecord::new()
+            .set_facility(record.facility.into_owned())
+            .set_file(record.file.into_owned())
+            .set_level(record.level)
+            .set_line(record.line)
+            .set_message(record.short_message.into_owned())
+            .set_timestamp(record.timestamp)
+            .set_timestamp_precision(record.timestamp_precision)
+            .set_ldp_suffixes(record.ldp_suffixes)
+            .set_full_message(record.full_message.map(Cow::into_owned))
+            .extend_additional_fields(record.additional_fields);
+        match record.host {
+            Some(host) => rec.set_host(host.into_owned()),
+            None => rec,
+        }
+    }
+}
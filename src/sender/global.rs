@@ -0,0 +1,114 @@
+// Copyright 2019-present, OVH SAS
+// All rights reserved.
+//
+// This OVH Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, RwLock};
+
+use crate::level::GelfLevel;
+use crate::record::GelfRecord;
+use crate::sender::GelfSink;
+
+static GLOBAL_SINK: RwLock<Option<Arc<dyn GelfSink>>> = RwLock::new(None);
+static HAS_GLOBAL_SINK: AtomicBool = AtomicBool::new(false);
+static GLOBAL_MAX_LEVEL: AtomicU32 = AtomicU32::new(GelfLevel::Debugging as u32);
+
+/// Register the sink used by [`emit`](fn.emit.html) and the level macros such as
+/// [`gelf_info!`](macro.gelf_info.html), replacing the previous one.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate serde_gelf;
+///
+/// use serde_gelf::StdoutSink;
+///
+/// fn main() {
+///     serde_gelf::set_global_sink(StdoutSink::new());
+///     gelf_info!(user_id = 5, "logged in");
+/// }
+/// ```
+pub fn set_global_sink<S: GelfSink + 'static>(sink: S) {
+    let mut global = GLOBAL_SINK.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    *global = Some(Arc::new(sink));
+    HAS_GLOBAL_SINK.store(true, Ordering::Relaxed);
+}
+
+/// Unregister the global sink, returning it so that it can be flushed.
+pub fn take_global_sink() -> Option<Arc<dyn GelfSink>> {
+    let mut global = GLOBAL_SINK.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    HAS_GLOBAL_SINK.store(false, Ordering::Relaxed);
+    global.take()
+}
+
+/// Set the least severe level sent by the level macros such as
+/// [`gelf_info!`](macro.gelf_info.html), `GelfLevel::Debugging` by default.
+///
+/// Records of a less severe level are neither formatted nor flattened, as well as every record
+/// while no global sink is registered.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate serde_gelf;
+///
+/// use std::fmt;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use serde_gelf::{GelfLevel, StdoutSink};
+///
+/// static FORMATTED: AtomicUsize = AtomicUsize::new(0);
+///
+/// struct Counted;
+///
+/// impl fmt::Display for Counted {
+///     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+///         FORMATTED.fetch_add(1, Ordering::SeqCst);
+///         f.write_str("counted")
+///     }
+/// }
+///
+/// fn main() {
+///     gelf_warn!("{}", Counted);
+///     assert_eq!(FORMATTED.load(Ordering::SeqCst), 0);
+///
+///     serde_gelf::set_global_sink(StdoutSink::new());
+///     serde_gelf::set_global_max_level(GelfLevel::Warning);
+///     gelf_info!("{}", Counted);
+///     assert_eq!(FORMATTED.load(Ordering::SeqCst), 0);
+///     gelf_warn!("{}", Counted);
+///     assert_eq!(FORMATTED.load(Ordering::SeqCst), 1);
+/// }
+/// ```
+pub fn set_global_max_level(level: GelfLevel) {
+    GLOBAL_MAX_LEVEL.store(level as u32, Ordering::Relaxed);
+}
+
+/// Return the least severe level sent by the level macros.
+pub fn global_max_level() -> GelfLevel {
+    GelfLevel::from(GLOBAL_MAX_LEVEL.load(Ordering::Relaxed))
+}
+
+/// Return whether a record of the given level would be sent by the level macros: a global sink
+/// is registered and the level is at least as severe as the
+/// [`global_max_level`](fn.global_max_level.html).
+pub fn global_enabled(level: GelfLevel) -> bool {
+    HAS_GLOBAL_SINK.load(Ordering::Relaxed) && level as u32 <= GLOBAL_MAX_LEVEL.load(Ordering::Relaxed)
+}
+
+/// Send a record to the global sink, records being discarded while no sink is registered.
+pub fn emit(record: &GelfRecord) -> io::Result<()> {
+    let sink = GLOBAL_SINK.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+    match sink {
+        Some(sink) => sink.send(record),
+        None => Ok(()),
+    }
+}
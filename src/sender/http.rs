@@ -0,0 +1,388 @@
+// Copyright 2019-present, OVH SAS
+// All rights reserved.
+//
+// This OVH Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "compression")]
+use crate::compression::Compression;
+use crate::record::GelfRecord;
+
+/// Default path of the Graylog GELF HTTP input.
+pub const DEFAULT_PATH: &str = "/gelf";
+/// Default number of retries on server errors.
+pub const DEFAULT_RETRIES: usize = 3;
+/// Default delay before the first retry.
+pub const DEFAULT_MIN_BACKOFF: Duration = Duration::from_millis(100);
+/// Default maximum delay between two retries.
+pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(5);
+/// Default connect, write and read timeout.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Headers managed by the sender, which can't be set using
+/// [`HttpSender::set_header`](struct.HttpSender.html#method.set_header).
+const RESERVED_HEADERS: [&str; 5] = ["Host", "Content-Length", "Content-Encoding", "Transfer-Encoding", "Connection"];
+
+/// Send [`GelfRecord`](struct.GelfRecord.html) to a Graylog GELF HTTP input, one `POST` per
+/// record.
+///
+/// Requests failing with a `5xx` status or a connection error are retried with an exponential
+/// backoff, blocking the caller, see [`AsyncSink`](struct.AsyncSink.html) to send in the
+/// background. Only `http://` URLs are supported.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate serde_gelf;
+///
+/// use std::io::{BufRead, BufReader, Read, Write};
+/// use std::net::TcpListener;
+/// use std::thread;
+/// use serde_gelf::HttpSender;
+///
+/// fn main() {
+///     // Stub GELF HTTP input, replying 503 then 202.
+///     let server = TcpListener::bind("127.0.0.1:0").unwrap();
+///     let url = format!("http://{}/gelf", server.local_addr().unwrap());
+///     let stub = thread::spawn(move || {
+///         let mut requests = Vec::new();
+///         for status in &["503 Service Unavailable", "202 Accepted"] {
+///             let (stream, _) = server.accept().unwrap();
+///             let mut reader = BufReader::new(stream);
+///             let mut head = String::new();
+///             while !head.ends_with("\r\n\r\n") {
+///                 reader.read_line(&mut head).unwrap();
+///             }
+///             let length = head.lines()
+///                 .find_map(|line| line.strip_prefix("Content-Length: "))
+///                 .map(|length| length.parse().unwrap())
+///                 .unwrap_or(0);
+///             let mut body = vec![0; length];
+///             reader.read_exact(&mut body).unwrap();
+///             write!(reader.get_mut(), "HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status).unwrap();
+///             requests.push((head, body));
+///         }
+///         requests
+///     });
+///
+///     let sender = HttpSender::new(&url).unwrap()
+///         .set_header("X-Tenant", "acme").unwrap()
+///         .set_header("Content-Type", "application/json; charset=utf-8").unwrap()
+///         .set_basic_auth("user", "secret");
+///     sender.send(&gelf_record!("hello")).unwrap();
+///
+///     let requests = stub.join().unwrap();
+///     let (head, body) = &requests[1];
+///     assert!(head.starts_with("POST /gelf HTTP/1.1\r\n"));
+///     assert!(head.contains("X-Tenant: acme\r\n"));
+///     assert_eq!(head.matches("Content-Type: ").collect::<Vec<_>>(), vec!["Content-Type: "]);
+///     assert!(head.contains("Content-Type: application/json; charset=utf-8\r\n"));
+///     assert!(head.contains("Authorization: Basic dXNlcjpzZWNyZXQ=\r\n"));
+///     let rec = serde_gelf::from_slice(body).unwrap();
+///     assert_eq!(serde_gelf::GelfRecordGetter::message(&rec), "hello");
+/// }
+/// ```
+#[derive(Debug)]
+pub struct HttpSender {
+    authority: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    retries: usize,
+    min_backoff: Duration,
+    max_backoff: Duration,
+    timeout: Duration,
+    #[cfg(feature = "compression")]
+    compression: Compression,
+}
+
+impl HttpSender {
+    /// Create a sender targeting the given `http://host[:port][/path][?query]` URL, the path
+    /// defaulting to `/gelf`. The fragment, if any, is not sent.
+    ///
+    /// Fails if the URL holds whitespace or control characters, which could otherwise split the
+    /// request.
+    ///
+    /// ```rust
+    /// use serde_gelf::HttpSender;
+    ///
+    /// assert!(HttpSender::new("http://127.0.0.1:12201/gelf?tenant=acme#top").is_ok());
+    /// assert!(HttpSender::new("http://127.0.0.1:12201?tenant=acme").is_ok());
+    /// assert!(HttpSender::new("http://127.0.0.1:12201/gelf HTTP/1.1\r\nX-Injected: 1").is_err());
+    /// assert!(HttpSender::new("http://127.0.0.1\r\nX-Injected: 1/gelf").is_err());
+    /// assert!(HttpSender::new("http://user@127.0.0.1/gelf").is_err());
+    /// assert!(HttpSender::new("https://127.0.0.1/gelf").is_err());
+    /// ```
+    pub fn new(url: &str) -> io::Result<HttpSender> {
+        let rest = url.strip_prefix("http://").ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "only http:// URLs are supported")
+        })?;
+        let invalid = || io::Error::new(io::ErrorKind::InvalidInput, format!("invalid GELF HTTP URL {:?}", url));
+        if rest.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return Err(invalid());
+        }
+        let rest = rest.split('#').next().unwrap_or_default();
+        let (authority, path) = rest.split_at(rest.find(['/', '?']).unwrap_or(rest.len()));
+        let path = match path {
+            "" => DEFAULT_PATH.to_string(),
+            query if query.starts_with('?') => format!("{}{}", DEFAULT_PATH, query),
+            path => path.to_string(),
+        };
+        if authority.is_empty() || authority.contains('@') {
+            return Err(invalid());
+        }
+        Ok(HttpSender {
+            authority: authority.to_string(),
+            path,
+            headers: Vec::new(),
+            retries: DEFAULT_RETRIES,
+            min_backoff: DEFAULT_MIN_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            timeout: DEFAULT_TIMEOUT,
+            #[cfg(feature = "compression")]
+            compression: Compression::default(),
+        })
+    }
+
+    /// Add a header sent with every request, replacing any header of the same name.
+    ///
+    /// `Content-Type` replaces the default `application/json`. Fails if the name or the value is
+    /// invalid, or if the header is managed by the sender such as `Content-Length`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use serde_gelf::HttpSender;
+    ///
+    /// let sender = HttpSender::new("http://127.0.0.1:12201/gelf").unwrap();
+    /// assert!(sender.set_header("X-Tenant", "acme\r\nX-Injected: 1").is_err());
+    ///
+    /// let sender = HttpSender::new("http://127.0.0.1:12201/gelf").unwrap();
+    /// assert!(sender.set_header("content-length", "0").is_err());
+    /// ```
+    pub fn set_header<N: Into<String>, V: Into<String>>(self, name: N, value: V) -> io::Result<Self> {
+        let (name, value) = (name.into(), value.into());
+        if name.is_empty() || name.contains(|c: char| c == ':' || c.is_ascii_control() || c.is_ascii_whitespace())
+            || value.contains(|c: char| c.is_ascii_control() && c != '\t') {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid HTTP header {:?}", name)));
+        }
+        if RESERVED_HEADERS.iter().any(|reserved| reserved.eq_ignore_ascii_case(&name)) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("HTTP header {} is set by the sender", name)));
+        }
+        Ok(self.insert_header(name, value))
+    }
+
+    /// Authenticate using HTTP basic authentication.
+    pub fn set_basic_auth(self, user: &str, password: &str) -> Self {
+        let credentials = base64(format!("{}:{}", user, password).as_bytes());
+        self.insert_header("Authorization".to_string(), format!("Basic {}", credentials))
+    }
+
+    fn insert_header(mut self, name: String, value: String) -> Self {
+        self.headers.retain(|(header, _)| !header.eq_ignore_ascii_case(&name));
+        self.headers.push((name, value));
+        self
+    }
+
+    /// Set the number of retries on server errors and connection failures.
+    pub fn set_retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Set the delay before the first retry and the maximum delay between two retries. The delay
+    /// doubles after each failed attempt.
+    pub fn set_backoff(mut self, min: Duration, max: Duration) -> Self {
+        self.min_backoff = min;
+        self.max_backoff = max.max(min);
+        self
+    }
+
+    /// Set the connect, write and read timeout.
+    pub fn set_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the compression applied to request bodies, sent as the `Content-Encoding` header.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use]
+    /// extern crate serde_gelf;
+    ///
+    /// use std::io::{BufRead, BufReader, Read, Write};
+    /// use std::net::TcpListener;
+    /// use std::thread;
+    /// use flate2::read::GzDecoder;
+    /// use serde_gelf::{Compression, HttpSender};
+    ///
+    /// fn main() {
+    ///     let server = TcpListener::bind("127.0.0.1:0").unwrap();
+    ///     let url = format!("http://{}/gelf", server.local_addr().unwrap());
+    ///     let stub = thread::spawn(move || {
+    ///         let (stream, _) = server.accept().unwrap();
+    ///         let mut reader = BufReader::new(stream);
+    ///         let mut head = String::new();
+    ///         while !head.ends_with("\r\n\r\n") {
+    ///             reader.read_line(&mut head).unwrap();
+    ///         }
+    ///         let length = head.lines()
+    ///             .find_map(|line| line.strip_prefix("Content-Length: "))
+    ///             .map(|length| length.parse().unwrap())
+    ///             .unwrap();
+    ///         let mut body = vec![0; length];
+    ///         reader.read_exact(&mut body).unwrap();
+    ///         write!(reader.get_mut(), "HTTP/1.1 202 Accepted\r\nContent-Length: 0\r\n\r\n").unwrap();
+    ///         (head, body)
+    ///     });
+    ///
+    ///     let sender = HttpSender::new(&url).unwrap()
+    ///         .set_compression(Compression::Gzip);
+    ///     let record = gelf_record!("hello");
+    ///     sender.send(&record).unwrap();
+    ///
+    ///     let (head, body) = stub.join().unwrap();
+    ///     assert!(head.contains("Content-Encoding: gzip\r\n"));
+    ///     let mut json = String::new();
+    ///     GzDecoder::new(&body[..]).read_to_string(&mut json).unwrap();
+    ///     assert_eq!(json, serde_json::to_string(&record).unwrap());
+    /// }
+    /// ```
+    #[cfg(feature = "compression")]
+    pub fn set_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Return the compression applied to request bodies.
+    #[cfg(feature = "compression")]
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// Serialize and send a record.
+    pub fn send(&self, record: &GelfRecord) -> io::Result<()> {
+        self.send_payload(&serde_json::to_vec(record)?)
+    }
+
+    /// Send an already serialized GELF payload, compressing it if required.
+    pub fn send_payload(&self, payload: &[u8]) -> io::Result<()> {
+        #[cfg(feature = "compression")]
+        let compressed = self.compression.compress(payload)?;
+        #[cfg(feature = "compression")]
+        let payload = &compressed[..];
+
+        let request = self.request(payload)?;
+        let mut backoff = self.min_backoff;
+        let mut attempt = 0;
+        loop {
+            match self.post(&request) {
+                Ok(()) => return Ok(()),
+                Err(Failure::Rejected(err)) => return Err(err),
+                Err(Failure::Retry(err)) if attempt >= self.retries => return Err(err),
+                Err(Failure::Retry(_)) => {
+                    thread::sleep(backoff);
+                    backoff = (backoff * 2).min(self.max_backoff);
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    /// Build the request, headers and body.
+    fn request(&self, body: &[u8]) -> io::Result<Vec<u8>> {
+        let mut request = Vec::with_capacity(256 + body.len());
+        write!(request, "POST {} HTTP/1.1\r\nHost: {}\r\n", self.path, self.authority)?;
+        if !self.headers.iter().any(|(name, _)| name.eq_ignore_ascii_case("Content-Type")) {
+            request.extend_from_slice(b"Content-Type: application/json\r\n");
+        }
+        write!(request, "Content-Length: {}\r\nConnection: close\r\n", body.len())?;
+        #[cfg(feature = "compression")]
+        match self.compression {
+            Compression::None => {}
+            Compression::Gzip => request.extend_from_slice(b"Content-Encoding: gzip\r\n"),
+            Compression::Zlib => request.extend_from_slice(b"Content-Encoding: deflate\r\n"),
+        }
+        for (name, value) in &self.headers {
+            write!(request, "{}: {}\r\n", name, value)?;
+        }
+        request.extend_from_slice(b"\r\n");
+        request.extend_from_slice(body);
+        Ok(request)
+    }
+
+    /// Send a request over a new connection and check the response status.
+    fn post(&self, request: &[u8]) -> Result<(), Failure> {
+        let mut stream = self.connect().map_err(Failure::Retry)?;
+        stream.write_all(request).map_err(Failure::Retry)?;
+        let mut status_line = String::new();
+        BufReader::new(stream).read_line(&mut status_line).map_err(Failure::Retry)?;
+        let status = status_line.split_whitespace().nth(1).and_then(|status| status.parse::<u16>().ok());
+        match status {
+            Some(200..=299) => Ok(()),
+            Some(500..=599) => Err(Failure::Retry(http_error(&status_line))),
+            Some(_) => Err(Failure::Rejected(http_error(&status_line))),
+            None => Err(Failure::Retry(io::Error::new(io::ErrorKind::InvalidData, "invalid HTTP response"))),
+        }
+    }
+
+    fn connect(&self) -> io::Result<TcpStream> {
+        let addr = match self.authority.rfind([':', ']']) {
+            Some(index) if self.authority[index..].starts_with(':') => self.authority.clone(),
+            _ => format!("{}:80", self.authority),
+        };
+        let mut last_err = io::Error::new(io::ErrorKind::InvalidInput, "no address to send GELF records to");
+        for addr in addr.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&addr, self.timeout) {
+                Ok(stream) => {
+                    stream.set_write_timeout(Some(self.timeout))?;
+                    stream.set_read_timeout(Some(self.timeout))?;
+                    return Ok(stream);
+                }
+                Err(err) => last_err = err,
+            }
+        }
+        Err(last_err)
+    }
+}
+
+/// Outcome of a failed request.
+enum Failure {
+    /// Server error or connection failure, worth retrying.
+    Retry(io::Error),
+    /// Request rejected by the server.
+    Rejected(io::Error),
+}
+
+fn http_error(status_line: &str) -> io::Error {
+    io::Error::other(format!("GELF HTTP input replied {}", status_line.trim_end()))
+}
+
+/// Encode bytes using the standard base64 alphabet, with padding.
+fn base64(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut output = String::with_capacity(input.len().div_ceil(3) * 4);
+    for group in input.chunks(3) {
+        let bytes = [group[0], *group.get(1).unwrap_or(&0), *group.get(2).unwrap_or(&0)];
+        let bits = u32::from(bytes[0]) << 16 | u32::from(bytes[1]) << 8 | u32::from(bytes[2]);
+        for index in 0..4 {
+            if index <= group.len() {
+                output.push(char::from(ALPHABET[(bits >> (18 - 6 * index) & 0x3f) as usize]));
+            } else {
+                output.push('=');
+            }
+        }
+    }
+    output
+}
@@ -0,0 +1,238 @@
+// Copyright 2019-present, OVH SAS
+// All rights reserved.
+//
+// This OVH Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::net::IpAddr;
+
+use crate::flatten::FlattenOptions;
+use crate::record::GelfRecord;
+use crate::sender::tcp::TcpSender;
+use crate::sender::tls::TlsOptions;
+use crate::sender::udp::UdpSender;
+
+/// Name of the additional field holding the LDP stream token.
+pub const LDP_TOKEN_FIELD: &str = "_X-OVH-TOKEN";
+
+/// Domain of the LDP clusters.
+const LDP_DOMAIN: &str = ".logs.ovh.com";
+
+/// Protocol used to reach the LDP cluster.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum LdpTransport {
+    /// GELF over TLS, port 12202.
+    #[default]
+    Tls,
+    /// GELF over TCP, port 2202.
+    Tcp,
+    /// GELF over UDP, port 2202.
+    Udp,
+}
+
+impl LdpTransport {
+    /// Return the GELF input port of the LDP clusters for this transport.
+    pub fn default_port(self) -> u16 {
+        match self {
+            LdpTransport::Tls => 12202,
+            LdpTransport::Tcp | LdpTransport::Udp => 2202,
+        }
+    }
+}
+
+/// Settings of a [`LdpSender`](struct.LdpSender.html).
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_gelf::{LdpOptions, LdpTransport};
+///
+/// let options = LdpOptions::new("gra1")
+///     .set_token("xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx")
+///     .set_transport(LdpTransport::Tls);
+/// assert_eq!(options.cluster(), "gra1.logs.ovh.com");
+/// assert_eq!(options.port(), 12202);
+/// ```
+#[derive(Debug, Clone)]
+pub struct LdpOptions {
+    cluster: String,
+    token: Option<String>,
+    transport: LdpTransport,
+    port: Option<u16>,
+}
+
+impl LdpOptions {
+    /// Create settings targeting the given LDP cluster.
+    ///
+    /// A cluster id such as `gra1` or `bhs1`, i.e. lowercase letters followed by digits, is
+    /// expanded to its host, `gra1.logs.ovh.com`. Any other name, such as a host or an IP
+    /// address, is used as is.
+    ///
+    /// ```rust
+    /// use serde_gelf::LdpOptions;
+    ///
+    /// assert_eq!(LdpOptions::new("gra2").cluster(), "gra2.logs.ovh.com");
+    /// assert_eq!(LdpOptions::new("bhs1").cluster(), "bhs1.logs.ovh.com");
+    /// assert_eq!(LdpOptions::new("gra2.logs.ovh.com").cluster(), "gra2.logs.ovh.com");
+    /// assert_eq!(LdpOptions::new("127.0.0.1").cluster(), "127.0.0.1");
+    /// assert_eq!(LdpOptions::new("::1").cluster(), "::1");
+    /// assert_eq!(LdpOptions::new("localhost").cluster(), "localhost");
+    /// ```
+    pub fn new(cluster: &str) -> LdpOptions {
+        let cluster = if cluster.parse::<IpAddr>().is_err() && is_cluster_id(cluster) {
+            format!("{}{}", cluster, LDP_DOMAIN)
+        } else {
+            cluster.to_string()
+        };
+        LdpOptions { cluster, token: None, transport: LdpTransport::default(), port: None }
+    }
+
+    /// Set the token of the LDP stream the records are sent to.
+    pub fn set_token(mut self, token: &str) -> Self {
+        self.token = Some(token.to_string());
+        self
+    }
+
+    /// Set the protocol used to reach the cluster, TLS by default.
+    pub fn set_transport(mut self, transport: LdpTransport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Override the port of the transport.
+    pub fn set_port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    /// Return the host of the LDP cluster.
+    pub fn cluster(&self) -> &str {
+        &self.cluster
+    }
+
+    /// Return the protocol used to reach the cluster.
+    pub fn transport(&self) -> LdpTransport {
+        self.transport
+    }
+
+    /// Return the port the records are sent to.
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or_else(|| self.transport.default_port())
+    }
+}
+
+/// Return whether a name is a LDP cluster id, i.e. lowercase letters followed by digits.
+fn is_cluster_id(name: &str) -> bool {
+    let letters = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    !letters.is_empty() && letters.len() < name.len() && letters.chars().all(|c| c.is_ascii_lowercase())
+}
+
+/// Underlying sender of a [`LdpSender`](struct.LdpSender.html).
+#[derive(Debug)]
+enum Transport {
+    Tcp(TcpSender),
+    Udp(UdpSender),
+}
+
+/// Send [`GelfRecord`](struct.GelfRecord.html) to an
+/// [`OVH Logs Data Platform`](https://docs.ovh.com/gb/en/logs-data-platform/) cluster.
+///
+/// The stream token is added to every record as the `_X-OVH-TOKEN` additional field, replacing
+/// any token the record already holds. LDP suffixes are always appended to the additional field
+/// names, whatever the [`set_ldp_suffixes`](struct.GelfRecord.html#method.set_ldp_suffixes)
+/// setting of the record.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate serde_gelf;
+///
+/// use std::net::UdpSocket;
+/// use serde_gelf::{GelfRecordBuilder, GelfRecordGetter, LdpOptions, LdpSender, LdpTransport};
+///
+/// fn main() {
+///     let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+///     let options = LdpOptions::new("127.0.0.1")
+///         .set_token("my-token")
+///         .set_transport(LdpTransport::Udp)
+///         .set_port(server.local_addr().unwrap().port());
+///     let sender = LdpSender::new(&options).unwrap();
+///
+///     // A token already held by the record is replaced and suffixes are always appended.
+///     let records = vec![
+///         gelf_record!("hello").add_additional_field("retries", &3),
+///         gelf_record!("hello").add_additional_field("retries", &3).add_additional_field("X-OVH-TOKEN", "other").set_ldp_suffixes(false),
+///     ];
+///     for record in records {
+///         sender.send(&record).unwrap();
+///
+///         let mut buf = [0; 1420];
+///         let size = server.recv(&mut buf).unwrap();
+///         let rec = serde_gelf::from_slice(&buf[..size]).unwrap();
+///         let fields = rec.additional_fields();
+///         assert_eq!(fields.get(&serde_value::Value::String("_X-OVH-TOKEN".into())), Some(&serde_value::Value::String("my-token".into())));
+///         assert!(fields.contains_key(&serde_value::Value::String("_retries_long".into())));
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct LdpSender {
+    token: BTreeMap<&'static str, String>,
+    transport: Transport,
+}
+
+impl LdpSender {
+    /// Create a sender using the given settings.
+    ///
+    /// Fails if no token is set, as LDP discards records without one.
+    ///
+    /// ```rust
+    /// use serde_gelf::{LdpOptions, LdpSender};
+    ///
+    /// assert!(LdpSender::new(&LdpOptions::new("gra1.logs.ovh.com")).is_err());
+    /// assert!(LdpSender::new(&LdpOptions::new("gra1.logs.ovh.com").set_token(" ")).is_err());
+    /// ```
+    pub fn new(options: &LdpOptions) -> io::Result<LdpSender> {
+        let token = match options.token {
+            Some(ref token) if !token.trim().is_empty() => token.clone(),
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, "missing LDP token")),
+        };
+        let addr = (options.cluster.as_str(), options.port());
+        let transport = match options.transport {
+            LdpTransport::Tls => Transport::Tcp(TcpSender::new(addr)?.set_tls(&TlsOptions::new(&options.cluster))?),
+            LdpTransport::Tcp => Transport::Tcp(TcpSender::new(addr)?),
+            LdpTransport::Udp => Transport::Udp(UdpSender::new(addr)?),
+        };
+
+        let mut fields = BTreeMap::new();
+        fields.insert(&LDP_TOKEN_FIELD[1..], token);
+        Ok(LdpSender { token: fields, transport })
+    }
+
+    /// Serialize a record with the stream token and send it.
+    pub fn send(&self, record: &GelfRecord) -> io::Result<()> {
+        let mut payload = Vec::with_capacity(256);
+        record
+            .write_ldp(&mut payload, &self.token, &FlattenOptions::default(), LDP_TOKEN_FIELD)
+            .map_err(io::Error::other)?;
+        match self.transport {
+            Transport::Tcp(ref sender) => sender.send_payload(&payload),
+            Transport::Udp(ref sender) => sender.send_payload(&payload),
+        }
+    }
+
+    /// Send the records buffered while the TCP connection was down.
+    pub fn flush(&self) -> io::Result<()> {
+        match self.transport {
+            Transport::Tcp(ref sender) => sender.flush(),
+            Transport::Udp(_) => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,73 @@
+// Copyright 2019-present, OVH SAS
+// All rights reserved.
+//
+// This OVH Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use std::io;
+use std::sync::Arc;
+
+use crate::record::GelfRecord;
+
+pub use self::global::{emit, global_enabled, global_max_level, set_global_max_level, set_global_sink, take_global_sink};
+pub use self::http::HttpSender;
+#[cfg(feature = "ovh-ldp-sender")]
+pub use self::ldp::{LdpOptions, LdpSender, LdpTransport, LDP_TOKEN_FIELD};
+pub use self::nonblocking::{AsyncGuard, AsyncSink, OverflowPolicy};
+pub use self::stream::{FileSink, StdoutSink};
+pub use self::tcp::TcpSender;
+#[cfg(feature = "tls")]
+pub use self::tls::TlsOptions;
+pub use self::udp::UdpSender;
+
+mod global;
+mod http;
+#[cfg(feature = "ovh-ldp-sender")]
+mod ldp;
+mod nonblocking;
+mod stream;
+mod tcp;
+#[cfg(feature = "tls")]
+mod tls;
+mod udp;
+
+/// Destination of [`GelfRecord`](struct.GelfRecord.html), such as a Graylog input or a file.
+pub trait GelfSink: Send + Sync {
+    /// Send a record.
+    fn send(&self, record: &GelfRecord) -> io::Result<()>;
+    /// Flush records buffered by the sink.
+    fn flush(&self) -> io::Result<()> { Ok(()) }
+}
+
+impl<S: GelfSink + ?Sized> GelfSink for Box<S> {
+    fn send(&self, record: &GelfRecord) -> io::Result<()> { (**self).send(record) }
+    fn flush(&self) -> io::Result<()> { (**self).flush() }
+}
+
+impl<S: GelfSink + ?Sized> GelfSink for Arc<S> {
+    fn send(&self, record: &GelfRecord) -> io::Result<()> { (**self).send(record) }
+    fn flush(&self) -> io::Result<()> { (**self).flush() }
+}
+
+impl GelfSink for UdpSender {
+    fn send(&self, record: &GelfRecord) -> io::Result<()> { UdpSender::send(self, record) }
+}
+
+impl GelfSink for HttpSender {
+    fn send(&self, record: &GelfRecord) -> io::Result<()> { HttpSender::send(self, record) }
+}
+
+#[cfg(feature = "ovh-ldp-sender")]
+impl GelfSink for LdpSender {
+    fn send(&self, record: &GelfRecord) -> io::Result<()> { LdpSender::send(self, record) }
+    fn flush(&self) -> io::Result<()> { LdpSender::flush(self) }
+}
+
+impl GelfSink for TcpSender {
+    fn send(&self, record: &GelfRecord) -> io::Result<()> { TcpSender::send(self, record) }
+    fn flush(&self) -> io::Result<()> { TcpSender::flush(self) }
+}
@@ -0,0 +1,315 @@
+// Copyright 2019-present, OVH SAS
+// All rights reserved.
+//
+// This OVH Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use std::collections::VecDeque;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread::{self, JoinHandle};
+
+use crate::record::GelfRecord;
+use crate::sender::GelfSink;
+
+/// What to do with a record when the queue of an [`AsyncSink`](struct.AsyncSink.html) is full.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate serde_gelf;
+///
+/// use std::io;
+/// use std::sync::mpsc::{channel, Receiver, Sender};
+/// use std::sync::{Arc, Mutex};
+/// use std::thread;
+/// use serde_gelf::{AsyncSink, GelfRecord, GelfRecordGetter, GelfSink, OverflowPolicy};
+///
+/// /// A sink sending a record each time it is released.
+/// struct SlowSink {
+///     started: Mutex<Sender<()>>,
+///     release: Mutex<Receiver<()>>,
+///     sent: Arc<Mutex<String>>,
+/// }
+///
+/// impl GelfSink for SlowSink {
+///     fn send(&self, record: &GelfRecord) -> io::Result<()> {
+///         self.started.lock().unwrap().send(()).unwrap();
+///         self.release.lock().unwrap().recv().unwrap();
+///         self.sent.lock().unwrap().push_str(&record.message());
+///         Ok(())
+///     }
+/// }
+///
+/// /// Send "1", "2" and "3" through a queue of one record, return what was sent and dropped.
+/// fn run(policy: OverflowPolicy) -> (String, u64) {
+///     let (started_tx, started) = channel();
+///     let (release, release_rx) = channel();
+///     let sent = Arc::new(Mutex::new(String::new()));
+///     let slow = SlowSink { started: Mutex::new(started_tx), release: Mutex::new(release_rx), sent: sent.clone() };
+///     let (sink, _guard) = AsyncSink::new(slow, 1, policy).unwrap();
+///
+///     // "1" is being sent and "2" fills the queue.
+///     sink.send(&gelf_record!("1")).unwrap();
+///     started.recv().unwrap();
+///     sink.send(&gelf_record!("2")).unwrap();
+///
+///     let producer = {
+///         let sink = sink.clone();
+///         thread::spawn(move || sink.send(&gelf_record!("3")).unwrap())
+///     };
+///     if policy == OverflowPolicy::Block {
+///         // "3" waits until "2" leaves the queue.
+///         release.send(()).unwrap();
+///         started.recv().unwrap();
+///     }
+///     producer.join().unwrap();
+///     for _ in 0..3 {
+///         release.send(()).unwrap();
+///     }
+///     sink.flush().unwrap();
+///     let sent = sent.lock().unwrap().clone();
+///     (sent, sink.dropped())
+/// }
+///
+/// fn main() {
+///     assert_eq!(run(OverflowPolicy::DropNewest), ("12".to_string(), 1));
+///     assert_eq!(run(OverflowPolicy::DropOldest), ("13".to_string(), 1));
+///     assert_eq!(run(OverflowPolicy::Block), ("123".to_string(), 0));
+/// }
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Discard the record being sent.
+    DropNewest,
+    /// Discard the oldest queued record to make room for the one being sent.
+    DropOldest,
+    /// Wait for the worker to make room in the queue.
+    Block,
+}
+
+/// Set the default policy to `OverflowPolicy::DropNewest`, logging never blocks the caller.
+impl Default for OverflowPolicy {
+    fn default() -> OverflowPolicy {
+        OverflowPolicy::DropNewest
+    }
+}
+
+/// A [`GelfSink`](trait.GelfSink.html) which queues records in a bounded queue consumed by a
+/// background thread, so slow sinks do not block the callers.
+///
+/// The worker runs until the [`AsyncGuard`](struct.AsyncGuard.html) is dropped, the queued
+/// records are then sent and the inner sink is flushed. A panic of the inner sink is caught and
+/// counted as a failed record, the worker keeps running.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate serde_gelf;
+///
+/// use serde_gelf::{AsyncSink, GelfSink, OverflowPolicy, StdoutSink};
+///
+/// fn main() {
+///     let (sink, _guard) = AsyncSink::new(StdoutSink::new(), 1024, OverflowPolicy::DropOldest).unwrap();
+///
+///     sink.send(&gelf_record!("hello")).unwrap();
+///     assert_eq!(sink.dropped(), 0);
+/// }
+/// ```
+#[derive(Clone)]
+pub struct AsyncSink {
+    shared: Arc<Shared>,
+}
+
+/// Stop the worker of an [`AsyncSink`](struct.AsyncSink.html) when dropped, after the queued
+/// records are sent.
+pub struct AsyncGuard {
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+struct Shared {
+    sink: Box<dyn GelfSink>,
+    capacity: usize,
+    policy: OverflowPolicy,
+    queue: Mutex<Queue>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    idle: Condvar,
+    dropped: AtomicU64,
+    failed: AtomicU64,
+}
+
+struct Queue {
+    records: VecDeque<GelfRecord>,
+    in_flight: usize,
+    closed: bool,
+}
+
+impl AsyncSink {
+    /// Spawn a worker sending the records to `sink`, queuing at most `capacity` records.
+    ///
+    /// Fails if the worker thread can't be spawned.
+    pub fn new<S: GelfSink + 'static>(sink: S, capacity: usize, policy: OverflowPolicy) -> io::Result<(AsyncSink, AsyncGuard)> {
+        let shared = Arc::new(Shared {
+            sink: Box::new(sink),
+            capacity: capacity.max(1),
+            policy,
+            queue: Mutex::new(Queue { records: VecDeque::new(), in_flight: 0, closed: false }),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            idle: Condvar::new(),
+            dropped: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
+        });
+        let worker = {
+            let shared = shared.clone();
+            thread::Builder::new()
+                .name("serde_gelf".into())
+                .spawn(move || shared.run())?
+        };
+        Ok((AsyncSink { shared: shared.clone() }, AsyncGuard { shared, worker: Some(worker) }))
+    }
+
+    /// Return the number of records discarded because the queue was full or the worker stopped.
+    pub fn dropped(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Return the number of records the inner sink failed to send.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use]
+    /// extern crate serde_gelf;
+    ///
+    /// use std::io;
+    /// use serde_gelf::{AsyncSink, GelfRecord, GelfSink, OverflowPolicy};
+    ///
+    /// struct BrokenSink;
+    ///
+    /// impl GelfSink for BrokenSink {
+    ///     fn send(&self, _: &GelfRecord) -> io::Result<()> {
+    ///         panic!("broken sink")
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let (sink, _guard) = AsyncSink::new(BrokenSink, 1, OverflowPolicy::Block).unwrap();
+    ///
+    ///     for _ in 0..3 {
+    ///         sink.send(&gelf_record!("hello")).unwrap();
+    ///     }
+    ///     sink.flush().unwrap();
+    ///     assert_eq!(sink.failed(), 3);
+    /// }
+    /// ```
+    pub fn failed(&self) -> u64 {
+        self.shared.failed.load(Ordering::Relaxed)
+    }
+
+    /// Return the number of records waiting to be sent.
+    pub fn queued(&self) -> usize {
+        self.shared.lock().records.len()
+    }
+}
+
+impl GelfSink for AsyncSink {
+    /// Queue a record, applying the overflow policy if the queue is full.
+    fn send(&self, record: &GelfRecord) -> io::Result<()> {
+        let shared = &self.shared;
+        let mut queue = shared.lock();
+        if queue.records.len() >= shared.capacity {
+            match shared.policy {
+                OverflowPolicy::DropNewest => {
+                    shared.dropped.fetch_add(1, Ordering::Relaxed);
+                    return Ok(());
+                }
+                OverflowPolicy::DropOldest => {
+                    queue.records.pop_front();
+                    shared.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                OverflowPolicy::Block => {
+                    while queue.records.len() >= shared.capacity && !queue.closed {
+                        queue = shared.not_full.wait(queue).unwrap_or_else(|poisoned| poisoned.into_inner());
+                    }
+                }
+            }
+        }
+        if queue.closed {
+            shared.dropped.fetch_add(1, Ordering::Relaxed);
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "GELF worker is stopped"));
+        }
+        queue.records.push_back(record.clone());
+        shared.not_empty.notify_one();
+        Ok(())
+    }
+
+    /// Wait for the queued records to be sent and flush the inner sink.
+    fn flush(&self) -> io::Result<()> {
+        let shared = &self.shared;
+        let mut queue = shared.lock();
+        while (!queue.records.is_empty() || queue.in_flight > 0) && !queue.closed {
+            queue = shared.idle.wait(queue).unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+        drop(queue);
+        shared.sink.flush()
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Worker loop, exits once the queue is closed and empty.
+    fn run(&self) {
+        loop {
+            let record = {
+                let mut queue = self.lock();
+                while queue.records.is_empty() && !queue.closed {
+                    queue = self.not_empty.wait(queue).unwrap_or_else(|poisoned| poisoned.into_inner());
+                }
+                match queue.records.pop_front() {
+                    Some(record) => {
+                        queue.in_flight += 1;
+                        self.not_full.notify_one();
+                        record
+                    }
+                    None => break,
+                }
+            };
+            let sent = panic::catch_unwind(AssertUnwindSafe(|| self.sink.send(&record)));
+            if !matches!(sent, Ok(Ok(()))) {
+                self.failed.fetch_add(1, Ordering::Relaxed);
+            }
+            let mut queue = self.lock();
+            queue.in_flight -= 1;
+            if queue.records.is_empty() && queue.in_flight == 0 {
+                self.idle.notify_all();
+            }
+        }
+        let _ = self.sink.flush();
+        self.idle.notify_all();
+    }
+}
+
+impl Drop for AsyncGuard {
+    fn drop(&mut self) {
+        self.shared.lock().closed = true;
+        self.shared.not_empty.notify_all();
+        self.shared.not_full.notify_all();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
@@ -0,0 +1,66 @@
+// Copyright 2019-present, OVH SAS
+// All rights reserved.
+//
+// This OVH Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::record::GelfRecord;
+use crate::sender::GelfSink;
+
+/// Write [`GelfRecord`](struct.GelfRecord.html) to the standard output, one JSON document per line.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StdoutSink;
+
+impl StdoutSink {
+    /// Create a sink writing to the standard output.
+    pub fn new() -> StdoutSink {
+        StdoutSink
+    }
+}
+
+impl GelfSink for StdoutSink {
+    fn send(&self, record: &GelfRecord) -> io::Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        io::stdout().lock().write_all(&line)
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        io::stdout().flush()
+    }
+}
+
+/// Append [`GelfRecord`](struct.GelfRecord.html) to a file, one JSON document per line.
+#[derive(Debug)]
+pub struct FileSink {
+    file: Mutex<File>,
+}
+
+impl FileSink {
+    /// Open the given file in append mode, creating it if required.
+    pub fn new<P: AsRef<Path>>(path: P) -> io::Result<FileSink> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(FileSink { file: Mutex::new(file) })
+    }
+}
+
+impl GelfSink for FileSink {
+    fn send(&self, record: &GelfRecord) -> io::Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        self.file.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).write_all(&line)
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        self.file.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).flush()
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright 2019-present, OVH SAS
+// All rights reserved.
+//
+// This OVH Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use std::convert::TryFrom;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use rustls::{ClientConfig, RootCertStore};
+
+/// TLS settings of a [`TcpSender`](struct.TcpSender.html).
+///
+/// Server certificates are verified against the Mozilla root certificates unless custom CA
+/// certificates are added, in which case only those are trusted.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_gelf::{TcpSender, TlsOptions};
+///
+/// let sender = TcpSender::new("127.0.0.1:12202").unwrap()
+///     .set_tls(&TlsOptions::new("gra1.logs.ovh.com"))
+///     .unwrap();
+/// ```
+#[derive(Debug)]
+pub struct TlsOptions {
+    server_name: String,
+    sni: bool,
+    roots: Vec<CertificateDer<'static>>,
+    client_auth: Option<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>)>,
+}
+
+impl TlsOptions {
+    /// Create TLS settings, `server_name` being used for SNI and to verify the server
+    /// certificate.
+    pub fn new(server_name: &str) -> TlsOptions {
+        TlsOptions { server_name: server_name.to_string(), sni: true, roots: Vec::new(), client_auth: None }
+    }
+
+    /// Enable or disable the Server Name Indication extension, enabled by default.
+    pub fn set_sni(mut self, sni: bool) -> Self {
+        self.sni = sni;
+        self
+    }
+
+    /// Trust the CA certificates of a PEM bundle.
+    pub fn add_ca_pem(mut self, pem: &[u8]) -> io::Result<Self> {
+        let certs = certificates(pem)?;
+        if certs.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "no certificate found in the CA bundle"));
+        }
+        self.roots.extend(certs);
+        Ok(self)
+    }
+
+    /// Trust the CA certificates of a PEM bundle file.
+    pub fn add_ca_pem_file<P: AsRef<Path>>(self, path: P) -> io::Result<Self> {
+        self.add_ca_pem(&fs::read(path)?)
+    }
+
+    /// Authenticate using a client certificate chain and its private key, both PEM encoded.
+    pub fn set_client_cert_pem(mut self, certs: &[u8], key: &[u8]) -> io::Result<Self> {
+        let certs = certificates(certs)?;
+        let key = PrivateKeyDer::from_pem_slice(key).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        self.client_auth = Some((certs, key));
+        Ok(self)
+    }
+
+    /// Authenticate using a client certificate chain file and its private key file, both PEM
+    /// encoded.
+    pub fn set_client_cert_pem_files<C: AsRef<Path>, K: AsRef<Path>>(self, certs: C, key: K) -> io::Result<Self> {
+        self.set_client_cert_pem(&fs::read(certs)?, &fs::read(key)?)
+    }
+
+    /// Build the rustls configuration and the server name.
+    pub(crate) fn client_config(&self) -> io::Result<(Arc<ClientConfig>, ServerName<'static>)> {
+        let server_name = ServerName::try_from(self.server_name.clone())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+        let mut roots = RootCertStore::empty();
+        if self.roots.is_empty() {
+            roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        }
+        for cert in &self.roots {
+            roots.add(cert.clone()).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        }
+
+        let builder = ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(io::Error::other)?
+            .with_root_certificates(roots);
+        let mut config = match self.client_auth {
+            Some((ref certs, ref key)) => builder
+                .with_client_auth_cert(certs.clone(), key.clone_key())
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+            None => builder.with_no_client_auth(),
+        };
+        config.enable_sni = self.sni;
+        Ok((Arc::new(config), server_name))
+    }
+}
+
+fn certificates(pem: &[u8]) -> io::Result<Vec<CertificateDer<'static>>> {
+    CertificateDer::pem_slice_iter(pem)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
@@ -0,0 +1,206 @@
+// Copyright 2019-present, OVH SAS
+// All rights reserved.
+//
+// This OVH Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+
+use crate::chunk::{chunks, CHUNK_HEADER_SIZE};
+#[cfg(feature = "compression")]
+use crate::compression::Compression;
+use crate::record::GelfRecord;
+
+/// Default maximum datagram size, suitable for WAN links.
+pub const DEFAULT_MTU: usize = 1420;
+
+/// Send [`GelfRecord`](struct.GelfRecord.html) over UDP.
+///
+/// Records larger than the MTU are split into GELF chunks, up to 128 chunks per record.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate serde_gelf;
+///
+/// use std::net::UdpSocket;
+/// use serde_gelf::UdpSender;
+///
+/// fn main() {
+///     let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+///     let sender = UdpSender::new(server.local_addr().unwrap()).unwrap();
+///
+///     sender.send(&gelf_record!("hello")).unwrap();
+///
+///     let mut buf = [0; 1420];
+///     let size = server.recv(&mut buf).unwrap();
+///     let rec = serde_gelf::from_slice(&buf[..size]).unwrap();
+///     assert_eq!(serde_gelf::GelfRecordGetter::message(&rec), "hello");
+/// }
+/// ```
+#[derive(Debug)]
+pub struct UdpSender {
+    socket: UdpSocket,
+    mtu: usize,
+    #[cfg(feature = "compression")]
+    compression: Compression,
+}
+
+impl UdpSender {
+    /// Create a sender targeting the given Graylog UDP input.
+    pub fn new<A: ToSocketAddrs>(addr: A) -> io::Result<UdpSender> {
+        let addr = addr.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "no address to send GELF records to")
+        })?;
+        let local: SocketAddr = match addr {
+            SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+            SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+        };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(addr)?;
+        Ok(UdpSender {
+            socket,
+            mtu: DEFAULT_MTU,
+            #[cfg(feature = "compression")]
+            compression: Compression::default(),
+        })
+    }
+
+    /// Set the maximum datagram size, chunk header included.
+    ///
+    /// The value is raised to leave room for at least one byte of payload per chunk.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use]
+    /// extern crate serde_gelf;
+    ///
+    /// use std::net::UdpSocket;
+    /// use serde_gelf::UdpSender;
+    ///
+    /// fn main() {
+    ///     let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+    ///     let sender = UdpSender::new(server.local_addr().unwrap()).unwrap().set_mtu(100);
+    ///
+    ///     sender.send(&gelf_record!("{}", "x".repeat(500))).unwrap();
+    ///
+    ///     let mut buf = [0; 100];
+    ///     let size = server.recv(&mut buf).unwrap();
+    ///     assert_eq!(size, 100);
+    ///     // Magic bytes, 8 bytes message id, sequence number and sequence count.
+    ///     assert_eq!(&buf[..2], &[0x1e, 0x0f]);
+    ///     assert_eq!(buf[10], 0);
+    ///     assert!(buf[11] > 1);
+    /// }
+    /// ```
+    pub fn set_mtu(mut self, mtu: usize) -> Self {
+        self.mtu = mtu.max(CHUNK_HEADER_SIZE + 1);
+        self
+    }
+
+    /// Return the maximum datagram size.
+    pub fn mtu(&self) -> usize {
+        self.mtu
+    }
+
+    /// Set the compression applied to payloads before they are chunked.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use]
+    /// extern crate serde_gelf;
+    ///
+    /// use std::io::Read;
+    /// use std::net::UdpSocket;
+    /// use flate2::read::{GzDecoder, ZlibDecoder};
+    /// use serde_gelf::{Compression, UdpSender};
+    ///
+    /// fn main() {
+    ///     let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+    ///     let record = gelf_record!("hello");
+    ///     let json = serde_json::to_string(&record).unwrap();
+    ///
+    ///     for &compression in &[Compression::Gzip, Compression::Zlib] {
+    ///         let sender = UdpSender::new(server.local_addr().unwrap()).unwrap()
+    ///             .set_compression(compression);
+    ///         sender.send(&record).unwrap();
+    ///
+    ///         let mut buf = [0; 1420];
+    ///         let size = server.recv(&mut buf).unwrap();
+    ///         let mut decompressed = String::new();
+    ///         match compression {
+    ///             Compression::Gzip => GzDecoder::new(&buf[..size]).read_to_string(&mut decompressed),
+    ///             _ => ZlibDecoder::new(&buf[..size]).read_to_string(&mut decompressed),
+    ///         }.unwrap();
+    ///         assert_eq!(decompressed, json);
+    ///     }
+    /// }
+    /// ```
+    #[cfg(feature = "compression")]
+    pub fn set_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Return the compression applied to payloads.
+    #[cfg(feature = "compression")]
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// Serialize and send a record, chunking it if required.
+    pub fn send(&self, record: &GelfRecord) -> io::Result<()> {
+        self.send_payload(&serde_json::to_vec(record)?)
+    }
+
+    /// Send an already serialized GELF payload, compressing and chunking it if required.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::net::UdpSocket;
+    /// use serde_gelf::UdpSender;
+    ///
+    /// let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+    /// let sender = UdpSender::new(server.local_addr().unwrap()).unwrap().set_mtu(100);
+    ///
+    /// // 88 bytes of data per chunk.
+    /// let payload: Vec<u8> = (0..500u32).map(|i| (i % 251) as u8).collect();
+    /// sender.send_payload(&payload).unwrap();
+    ///
+    /// let mut parts = vec![Vec::new(); 6];
+    /// let mut id = None;
+    /// for _ in 0..6 {
+    ///     let mut buf = [0; 100];
+    ///     let size = server.recv(&mut buf).unwrap();
+    ///     assert_eq!(&buf[..2], &[0x1e, 0x0f]);
+    ///     assert_eq!(*id.get_or_insert(buf[2..10].to_vec()), buf[2..10].to_vec());
+    ///     assert_eq!(buf[11], 6);
+    ///     parts[buf[10] as usize] = buf[12..size].to_vec();
+    /// }
+    /// assert_eq!(parts.concat(), payload);
+    ///
+    /// // GELF allows at most 128 chunks per message.
+    /// assert!(sender.send_payload(&vec![b'x'; 88 * 128]).is_ok());
+    /// assert!(sender.send_payload(&vec![b'x'; 88 * 128 + 1]).is_err());
+    /// ```
+    pub fn send_payload(&self, payload: &[u8]) -> io::Result<()> {
+        #[cfg(feature = "compression")]
+        let compressed = self.compression.compress(payload)?;
+        #[cfg(feature = "compression")]
+        let payload = &compressed[..];
+
+        for datagram in chunks(payload, self.mtu)? {
+            self.socket.send(&datagram)?;
+        }
+        Ok(())
+    }
+}
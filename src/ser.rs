@@ -0,0 +1,286 @@
+// Copyright 2019-present, OVH SAS
+// All rights reserved.
+//
+// This OVH Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Serializer writing flattened entries straight into a `SerializeMap`, producing the same
+//! entries as [`FlattenOptions::flatten`](struct.FlattenOptions.html#method.flatten) without
+//! building `serde_value::Value` trees, except for the subtrees which are JSON encoded or joined.
+
+use std::collections::{BTreeMap, HashSet};
+
+use serde::ser::{self, Error, Serialize, SerializeMap};
+use serde_value::Value;
+
+use crate::flatten::{key_to_string, FlattenOptions};
+
+/// Serialize a value as flattened entries of `map`, failing on a key already written or found in
+/// `keys`.
+pub(crate) fn serialize_flat_entries<M, S>(map: &mut M, value: &S, options: &FlattenOptions, mut keys: HashSet<String>) -> Result<(), M::Error>
+    where M: SerializeMap, S: Serialize + ?Sized {
+    options.check()?;
+    value.serialize(FlatSerializer { map, options, path: &mut Vec::new(), keys: &mut keys })
+}
+
+/// Serializer flattening a value found at `path`.
+struct FlatSerializer<'a, M: 'a> {
+    map: &'a mut M,
+    options: &'a FlattenOptions,
+    path: &'a mut Vec<String>,
+    keys: &'a mut HashSet<String>,
+}
+
+impl<'a, M: SerializeMap> FlatSerializer<'a, M> {
+    fn emit<T: Serialize + ?Sized>(self, value: &T, suffix: &str) -> Result<(), M::Error> {
+        let key = self.options.key(self.path, suffix);
+        if self.keys.contains(&key) {
+            return Err(M::Error::custom(format!("duplicate field `{}`", key)));
+        }
+        self.map.serialize_entry(&key, value)?;
+        self.keys.insert(key);
+        Ok(())
+    }
+
+    /// Flatten an already built value, used for the subtrees which are JSON encoded or joined.
+    fn emit_value(self, value: &Value) -> Result<(), M::Error> {
+        let mut flat = BTreeMap::new();
+        self.options.disassemble(self.path, value, &mut flat).map_err(M::Error::custom)?;
+        for (key, value) in flat {
+            let key = match key {
+                Value::String(key) => key,
+                other => return Err(M::Error::custom(format!("map keys must be strings, got {:?}", other))),
+            };
+            if self.keys.contains(&key) {
+                return Err(M::Error::custom(format!("duplicate field `{}`", key)));
+            }
+            self.map.serialize_entry(&key, &value)?;
+            self.keys.insert(key);
+        }
+        Ok(())
+    }
+
+    fn compound(self, collect: bool) -> FlatCompound<'a, M> {
+        let state = match collect {
+            true => State::Collect(Vec::new(), BTreeMap::new(), None),
+            false => State::Flatten(0, None),
+        };
+        FlatCompound { ser: self, state, is_map: false }
+    }
+
+    fn map_compound(self) -> FlatCompound<'a, M> {
+        let collect = self.options.is_too_deep(self.path);
+        FlatCompound { is_map: true, ..self.compound(collect) }
+    }
+
+    fn seq_compound(self) -> FlatCompound<'a, M> {
+        let collect = self.options.is_too_deep(self.path) || !self.options.indexes_arrays();
+        self.compound(collect)
+    }
+}
+
+/// State of a map or sequence being serialized.
+enum State {
+    /// Flatten each item, tracking the next sequence index and the pending map key.
+    Flatten(usize, Option<String>),
+    /// Build the sequence items or the map entries, with the pending map key.
+    Collect(Vec<Value>, BTreeMap<Value, Value>, Option<Value>),
+}
+
+struct FlatCompound<'a, M: 'a> {
+    ser: FlatSerializer<'a, M>,
+    state: State,
+    is_map: bool,
+}
+
+impl<'a, M: SerializeMap> FlatCompound<'a, M> {
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), M::Error> {
+        match self.state {
+            State::Flatten(ref mut index, _) => {
+                let segment = index.to_string();
+                *index += 1;
+                self.nested(segment, value)
+            }
+            State::Collect(ref mut items, _, _) => {
+                items.push(serde_value::to_value(value).map_err(M::Error::custom)?);
+                Ok(())
+            }
+        }
+    }
+
+    fn field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), M::Error> {
+        match self.state {
+            State::Flatten(_, _) => self.nested(key.to_string(), value),
+            State::Collect(_, ref mut entries, _) => {
+                entries.insert(Value::String(key.to_string()), serde_value::to_value(value).map_err(M::Error::custom)?);
+                Ok(())
+            }
+        }
+    }
+
+    fn nested<T: Serialize + ?Sized>(&mut self, segment: String, value: &T) -> Result<(), M::Error> {
+        self.ser.path.push(segment);
+        let result = value.serialize(FlatSerializer {
+            map: &mut *self.ser.map,
+            options: self.ser.options,
+            path: &mut *self.ser.path,
+            keys: &mut *self.ser.keys,
+        });
+        self.ser.path.pop();
+        result
+    }
+
+    fn finish(self) -> Result<(), M::Error> {
+        match self.state {
+            State::Flatten(_, _) => Ok(()),
+            State::Collect(items, entries, _) => match self.is_map {
+                true => self.ser.emit_value(&Value::Map(entries)),
+                false => self.ser.emit_value(&Value::Seq(items)),
+            },
+        }
+    }
+}
+
+macro_rules! emit {
+    ($($method:ident: $ty:ty => $suffix:expr,)*) => {
+        $(fn $method(self, v: $ty) -> Result<(), M::Error> { self.emit(&v, $suffix) })*
+    };
+}
+
+impl<'a, M: SerializeMap> ser::Serializer for FlatSerializer<'a, M> {
+    type Ok = ();
+    type Error = M::Error;
+    type SerializeSeq = FlatCompound<'a, M>;
+    type SerializeTuple = FlatCompound<'a, M>;
+    type SerializeTupleStruct = FlatCompound<'a, M>;
+    type SerializeTupleVariant = FlatCompound<'a, M>;
+    type SerializeMap = FlatCompound<'a, M>;
+    type SerializeStruct = FlatCompound<'a, M>;
+    type SerializeStructVariant = FlatCompound<'a, M>;
+
+    emit! {
+        serialize_bool: bool => "_bool",
+        serialize_i8: i8 => "_long",
+        serialize_i16: i16 => "_long",
+        serialize_i32: i32 => "_long",
+        serialize_i64: i64 => "_long",
+        serialize_u8: u8 => "_double",
+        serialize_u16: u16 => "_double",
+        serialize_u32: u32 => "_double",
+        serialize_u64: u64 => "_double",
+        serialize_f32: f32 => "_float",
+        serialize_f64: f64 => "_float",
+        serialize_char: char => "",
+        serialize_str: &str => "",
+        serialize_bytes: &[u8] => "",
+    }
+
+    fn serialize_none(self) -> Result<(), M::Error> { self.emit(&(), "") }
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), M::Error> { value.serialize(self) }
+    fn serialize_unit(self) -> Result<(), M::Error> { self.emit(&(), "") }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), M::Error> { self.emit(&(), "") }
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, _variant: &'static str) -> Result<(), M::Error> {
+        self.emit(&(), "")
+    }
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<(), M::Error> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _index: u32, _variant: &'static str, value: &T) -> Result<(), M::Error> {
+        value.serialize(self)
+    }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, M::Error> { Ok(self.seq_compound()) }
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, M::Error> { Ok(self.seq_compound()) }
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct, M::Error> {
+        Ok(self.seq_compound())
+    }
+    fn serialize_tuple_variant(self, _name: &'static str, _index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeTupleVariant, M::Error> {
+        Ok(self.seq_compound())
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, M::Error> { Ok(self.map_compound()) }
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct, M::Error> {
+        Ok(self.map_compound())
+    }
+    fn serialize_struct_variant(self, _name: &'static str, _index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeStructVariant, M::Error> {
+        Ok(self.map_compound())
+    }
+}
+
+impl<'a, M: SerializeMap> ser::SerializeSeq for FlatCompound<'a, M> {
+    type Ok = ();
+    type Error = M::Error;
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), M::Error> { self.element(value) }
+    fn end(self) -> Result<(), M::Error> { self.finish() }
+}
+
+impl<'a, M: SerializeMap> ser::SerializeTuple for FlatCompound<'a, M> {
+    type Ok = ();
+    type Error = M::Error;
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), M::Error> { self.element(value) }
+    fn end(self) -> Result<(), M::Error> { self.finish() }
+}
+
+impl<'a, M: SerializeMap> ser::SerializeTupleStruct for FlatCompound<'a, M> {
+    type Ok = ();
+    type Error = M::Error;
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), M::Error> { self.element(value) }
+    fn end(self) -> Result<(), M::Error> { self.finish() }
+}
+
+impl<'a, M: SerializeMap> ser::SerializeTupleVariant for FlatCompound<'a, M> {
+    type Ok = ();
+    type Error = M::Error;
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), M::Error> { self.element(value) }
+    fn end(self) -> Result<(), M::Error> { self.finish() }
+}
+
+impl<'a, M: SerializeMap> ser::SerializeStruct for FlatCompound<'a, M> {
+    type Ok = ();
+    type Error = M::Error;
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), M::Error> {
+        self.field(key, value)
+    }
+    fn end(self) -> Result<(), M::Error> { self.finish() }
+}
+
+impl<'a, M: SerializeMap> ser::SerializeStructVariant for FlatCompound<'a, M> {
+    type Ok = ();
+    type Error = M::Error;
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), M::Error> {
+        self.field(key, value)
+    }
+    fn end(self) -> Result<(), M::Error> { self.finish() }
+}
+
+impl<'a, M: SerializeMap> ser::SerializeMap for FlatCompound<'a, M> {
+    type Ok = ();
+    type Error = M::Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), M::Error> {
+        let key = serde_value::to_value(key).map_err(M::Error::custom)?;
+        match self.state {
+            State::Flatten(_, ref mut pending) => *pending = Some(key_to_string(&key).map_err(M::Error::custom)?),
+            State::Collect(_, _, ref mut pending) => *pending = Some(key),
+        }
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), M::Error> {
+        match self.state {
+            State::Flatten(_, ref mut pending) => {
+                let segment = pending.take().ok_or_else(|| M::Error::custom("map value without key"))?;
+                self.nested(segment, value)
+            }
+            State::Collect(_, ref mut entries, ref mut pending) => {
+                let key = pending.take().ok_or_else(|| M::Error::custom("map value without key"))?;
+                entries.insert(key, serde_value::to_value(value).map_err(M::Error::custom)?);
+                Ok(())
+            }
+        }
+    }
+
+    fn end(self) -> Result<(), M::Error> { self.finish() }
+}
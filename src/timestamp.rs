@@ -0,0 +1,101 @@
+// Copyright 2019-present, OVH SAS
+// All rights reserved.
+//
+// This OVH Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::ser::{Serialize, Serializer};
+
+/// Precision of the serialized `GelfRecord.timestamp`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TimestampPrecision {
+    /// Whole seconds, serialized as an integer.
+    Seconds,
+    /// Seconds with 3 decimal places.
+    Millis,
+    /// Seconds with 6 decimal places.
+    Micros,
+}
+
+impl TimestampPrecision {
+    /// Truncate a timestamp to the precision.
+    pub fn truncate(self, timestamp: f64) -> f64 {
+        match self {
+            TimestampPrecision::Seconds => timestamp.floor(),
+            TimestampPrecision::Millis => (timestamp * 1e3).floor() / 1e3,
+            TimestampPrecision::Micros => (timestamp * 1e6).floor() / 1e6,
+        }
+    }
+}
+
+/// A timestamp serialized with an optional precision.
+pub struct PreciseTimestamp(pub f64, pub Option<TimestampPrecision>);
+
+impl Serialize for PreciseTimestamp {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        match self.1 {
+            None => serializer.serialize_f64(self.0),
+            Some(TimestampPrecision::Seconds) => serializer.serialize_i64(self.0.floor() as i64),
+            Some(precision) => serializer.serialize_f64(precision.truncate(self.0)),
+        }
+    }
+}
+
+/// Convert a `SystemTime` into seconds since UNIX epoch.
+pub fn from_system_time(time: SystemTime) -> f64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(elapsed) => elapsed.as_secs_f64(),
+        Err(err) => -err.duration().as_secs_f64(),
+    }
+}
+
+/// Convert seconds since UNIX epoch into a `SystemTime`, invalid timestamps giving UNIX epoch.
+pub fn to_system_time(timestamp: f64) -> SystemTime {
+    match Duration::try_from_secs_f64(timestamp.abs()) {
+        Ok(elapsed) if timestamp >= 0.0 => UNIX_EPOCH + elapsed,
+        Ok(elapsed) => UNIX_EPOCH - elapsed,
+        Err(_) => UNIX_EPOCH,
+    }
+}
+
+/// Convert a `chrono::DateTime<Utc>` into seconds since UNIX epoch.
+#[cfg(feature = "chrono")]
+pub fn from_chrono(datetime: chrono::DateTime<chrono::Utc>) -> f64 {
+    datetime.timestamp() as f64 + f64::from(datetime.timestamp_subsec_nanos()) / 1e9
+}
+
+/// Convert a `time::OffsetDateTime` into seconds since UNIX epoch.
+#[cfg(feature = "time")]
+pub fn from_offset_datetime(datetime: time::OffsetDateTime) -> f64 {
+    datetime.unix_timestamp() as f64 + f64::from(datetime.nanosecond()) / 1e9
+}
+
+/// Types which can be used as a `GelfRecord.timestamp`, used by the `GelfRecord` derive.
+pub trait AsTimestamp {
+    /// Return the seconds since UNIX epoch.
+    fn as_timestamp(&self) -> f64;
+}
+
+impl AsTimestamp for f64 {
+    fn as_timestamp(&self) -> f64 { *self }
+}
+
+impl AsTimestamp for SystemTime {
+    fn as_timestamp(&self) -> f64 { from_system_time(*self) }
+}
+
+#[cfg(feature = "chrono")]
+impl AsTimestamp for chrono::DateTime<chrono::Utc> {
+    fn as_timestamp(&self) -> f64 { from_chrono(*self) }
+}
+
+#[cfg(feature = "time")]
+impl AsTimestamp for time::OffsetDateTime {
+    fn as_timestamp(&self) -> f64 { from_offset_datetime(*self) }
+}
@@ -0,0 +1,70 @@
+// Copyright 2019-present, OVH SAS
+// All rights reserved.
+//
+// This OVH Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use std::collections::BTreeMap;
+
+use serde_value::Value;
+
+/// Reserved additional field name, Graylog drops messages using it.
+pub const RESERVED_FIELD: &str = "_id";
+
+/// Return whether a character matches `[\w\.\-]`, `\w` being ASCII only as in Graylog.
+fn is_valid_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '-'
+}
+
+/// Check an additional field name: it must be prefixed with an underscore, match
+/// `^[\w\.\-]*$` and not be the reserved `_id` field.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_gelf::is_valid_field_name;
+///
+/// assert!(is_valid_field_name("_user.id"));
+/// assert!(!is_valid_field_name("_user id"));
+/// assert!(!is_valid_field_name("user_id"));
+/// assert!(!is_valid_field_name("_id"));
+/// ```
+pub fn is_valid_field_name(name: &str) -> bool {
+    name.starts_with('_') && name != RESERVED_FIELD && name.chars().all(is_valid_char)
+}
+
+/// Rewrite an additional field name to make it valid: illegal characters are replaced by
+/// underscores, a missing underscore prefix is added and `_id` is renamed `__id`.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde_gelf::sanitize_field_name;
+///
+/// assert_eq!(sanitize_field_name("_user id"), "_user_id");
+/// assert_eq!(sanitize_field_name("user"), "_user");
+/// assert_eq!(sanitize_field_name("_id"), "__id");
+/// ```
+pub fn sanitize_field_name(name: &str) -> String {
+    let mut sanitized: String = name.chars().map(|c| if is_valid_char(c) { c } else { '_' }).collect();
+    if !sanitized.starts_with('_') {
+        sanitized.insert(0, '_');
+    }
+    if sanitized == RESERVED_FIELD {
+        sanitized.insert(0, '_');
+    }
+    sanitized
+}
+
+/// Return a representation of each invalid key of an additional fields dict.
+pub fn invalid_fields(additional_fields: &BTreeMap<Value, Value>) -> Vec<String> {
+    additional_fields.keys().filter_map(|key| match key {
+        Value::String(name) if is_valid_field_name(name) => None,
+        Value::String(name) => Some(name.clone()),
+        other => Some(format!("{:?}", other)),
+    }).collect()
+}